@@ -0,0 +1,320 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::model::{CombinationRule, Finding, Mistake, Tokens, TokenStream};
+use crate::traits::Detector;
+
+/// Runs multiple detectors on the same text and merges their results.
+///
+/// The text is tokenized only once, every detector processes the same TokenStream in a single pass.
+///
+/// Mistakes reported at the same place (see Finding::is_same_place) by different detectors are
+/// merged into a single Finding, whose probability is calculated using the given CombinationRule. The findings
+/// are ranked: the most probable one comes first, findings with the same probability are in the
+/// order they appear in the text. Findings whose probability is not a number come last.
+pub struct Analyzer {
+    detectors: Vec<(String, Box<dyn Detector>)>,
+    rule: CombinationRule,
+}
+
+impl Analyzer {
+    pub fn new(rule: CombinationRule) -> Analyzer {
+        Analyzer {
+            detectors: Vec::new(),
+            rule,
+        }
+    }
+
+    /// Adds a detector, the name is used to identify it in the sources of the findings.
+    pub fn add_detector(&mut self, name: &str, detector: Box<dyn Detector>) {
+        self.detectors.push((String::from(name), detector));
+    }
+
+    pub fn analyze(&mut self, text: &str) -> Vec<Finding> {
+        self.analyze_stream(&TokenStream::new(text))
+    }
+
+    pub fn analyze_stream(&mut self, tokens: &TokenStream) -> Vec<Finding> {
+        let mut errors = vec![Vec::new(); self.detectors.len()];
+
+        self.detectors.iter_mut().for_each(|(_, detector)| detector.reset());
+        for token in tokens.iter() {
+            for ((_, detector), errors) in self.detectors.iter_mut().zip(errors.iter_mut()) {
+                detector.next_token(token, errors);
+            }
+        }
+        for ((_, detector), errors) in self.detectors.iter_mut().zip(errors.iter_mut()) {
+            detector.end_of_row(errors);
+            detector.end_of_text(errors);
+        }
+
+        let mut findings = self.merge(errors);
+
+        findings.sort_by(|a, b| {
+            a.prob.is_nan().cmp(&b.prob.is_nan())
+                .then(b.prob.total_cmp(&a.prob))
+                .then(a.row.cmp(&b.row))
+                .then(a.col.cmp(&b.col))
+        });
+
+        findings
+    }
+
+    /// Analyzes the prose of a Markdown document, the positions of the findings are in the
+    /// Markdown source (see markdown::prose_tokens).
+    #[cfg(feature = "markdown")]
+    pub fn analyze_markdown(&mut self, source: &str) -> Vec<Finding> {
+        self.analyze_stream(&TokenStream::from_tokens(crate::markdown::prose_tokens(source)))
+    }
+
+    /// Finds the mistakes lazily, the findings are yielded sentence by sentence (see FindingIter).
+    pub fn analyze_iter<'t>(&mut self, text: &'t str) -> FindingIter<'_, 't> {
+        self.detectors.iter_mut().for_each(|(_, detector)| detector.reset());
+
+        FindingIter {
+            analyzer: self,
+            tokens: Tokens::new(text),
+            findings: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Merges the mistakes reported at the same place, `errors` contains the mistakes of each
+    /// detector. The sources of a finding are in the order of the detectors.
+    fn merge(&self, errors: Vec<Vec<(usize, usize, Mistake)>>) -> Vec<Finding> {
+        let mut findings: Vec<Finding> = Vec::new();
+        let mut places: HashMap<_, usize> = HashMap::new();
+        for ((name, _), errors) in self.detectors.iter().zip(errors) {
+            for (row, col, mistake) in errors {
+                match places.get(&Finding::place_key(row, col, &mistake)) {
+                    Some(&index) => findings[index].sources.push((name.clone(), mistake)),
+                    None => {
+                        places.insert(Finding::place_key(row, col, &mistake), findings.len());
+                        findings.push(Finding::new(row, col, name, mistake));
+                    }
+                }
+            }
+        }
+
+        for finding in findings.iter_mut() {
+            finding.prob = self.rule.combine(finding.sources.iter().map(|(_, a)| a.prob));
+        }
+
+        findings
+    }
+}
+
+/// The findings of an Analyzer, computed lazily.
+///
+/// The detectors report every mistake of a sentence by its end, so the text is processed sentence
+/// by sentence: the findings of a sentence are merged and yielded (in the order they appear in the
+/// text, not ranked) before the next sentence is processed. The iteration can be stopped early
+/// without processing the rest of the text.
+pub struct FindingIter<'a, 't> {
+    analyzer: &'a mut Analyzer,
+    tokens: Tokens<'t>,
+    findings: VecDeque<Finding>,
+    finished: bool,
+}
+
+impl<'a, 't> FindingIter<'a, 't> {
+    /// Processes the next sentence (or the rest of the text) and queues its findings.
+    fn next_sentence(&mut self) {
+        let detectors = &mut self.analyzer.detectors;
+        let mut errors = vec![Vec::new(); detectors.len()];
+
+        loop {
+            match self.tokens.next() {
+                Some(token) => {
+                    for ((_, detector), errors) in detectors.iter_mut().zip(errors.iter_mut()) {
+                        detector.next_token(&token, errors);
+                    }
+                    if token.kind.ends_sentence() {
+                        break;
+                    }
+                }
+                None => {
+                    for ((_, detector), errors) in detectors.iter_mut().zip(errors.iter_mut()) {
+                        detector.end_of_row(errors);
+                        detector.end_of_text(errors);
+                    }
+                    self.finished = true;
+                    break;
+                }
+            }
+        }
+
+        let mut findings = self.analyzer.merge(errors);
+        findings.sort_by(|a, b| a.row.cmp(&b.row).then(a.col.cmp(&b.col)));
+        self.findings.extend(findings);
+    }
+}
+
+impl<'a, 't> Iterator for FindingIter<'a, 't> {
+    type Item = Finding;
+
+    fn next(&mut self) -> Option<Finding> {
+        while self.findings.is_empty() && !self.finished {
+            self.next_sentence();
+        }
+
+        self.findings.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::Analyzer;
+    use crate::detector::{NaiveDetector, NaiveForwardDetector, PairDetector, SuperfluousDetector, TypicalDetector};
+    use crate::model::{CombinationRule, Mistake, NaiveSettings, PairSettings, SuperfluousSettings, Token, TypicalSettings};
+    use crate::traits::Detector;
+
+    /// Reports every word with a probability which is not a number.
+    struct NanDetector;
+
+    impl Detector for NanDetector {
+        fn reset(&mut self) {}
+
+        fn next_token(&mut self, token: &Token, errors: &mut Vec<(usize, usize, Mistake)>) {
+            if token.kind.is_word() {
+                errors.push((token.position.line, token.position.col, Mistake::new("nan", f64::NAN).with_position(token.position.clone())));
+            }
+        }
+
+        fn end_of_row(&mut self, _errors: &mut Vec<(usize, usize, Mistake)>) {}
+    }
+
+    fn analyzer(rule: CombinationRule) -> Analyzer {
+        let mut analyzer = Analyzer::new(rule);
+        analyzer.add_detector("naive", Box::new(NaiveDetector::new(NaiveSettings { words: vec![String::from("akkor"), String::from("hogy")], probs: vec![0.5, 0.9], exceptions: Vec::new() }).unwrap()));
+        analyzer.add_detector("pair", Box::new(PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![0.6], options: Vec::new(), further_parts: Vec::new() }).unwrap()));
+        analyzer.add_detector("typical", Box::new(TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem")], probs: vec![0.3] }).unwrap()));
+
+        analyzer
+    }
+
+    #[test]
+    fn empty_str() {
+        let findings = analyzer(CombinationRule::NoisyOr).analyze("");
+
+        assert_eq!(findings.len(), 0);
+    }
+
+    #[test]
+    fn noisy_or_merges_same_place() {
+        let findings = analyzer(CombinationRule::NoisyOr).analyze("Ha mész vásárolni akkor hozz tejet!");
+
+        assert_eq!(findings.len(), 1);
+        assert!((findings[0].prob - 0.8).abs() < 1e-9);
+        assert_eq!(findings[0].sources.iter().map(|(a, _)| a.as_str()).collect::<Vec<&str>>(), vec!["naive", "pair"]);
+        assert_eq!(findings[0].insert_at.as_ref().map(|a| a.span.clone()), Some(20..20));
+    }
+
+    #[test]
+    fn max_merges_same_place() {
+        let findings = analyzer(CombinationRule::Max).analyze("Ha mész vásárolni akkor hozz tejet!");
+
+        assert_eq!(findings.len(), 1);
+        assert!((findings[0].prob - 0.6).abs() < 1e-9);
+    }
+
+    #[test]
+    fn nan_probability_ranked() {
+        let mut analyzer = analyzer(CombinationRule::NoisyOr);
+        analyzer.add_detector("nan", Box::new(NanDetector));
+        let findings = analyzer.analyze("Azt mondta hogy jön.");
+
+        assert_eq!(findings.len(), 5);
+        assert_eq!(findings[0].prob, 0.9);
+        assert!(findings[1..].iter().all(|a| a.prob.is_nan()));
+        assert_eq!(findings[1..].iter().map(|a| a.col).collect::<Vec<_>>(), vec![1, 5, 12, 17]);
+    }
+
+    #[test]
+    fn many_findings_merged() {
+        let text = "Azt mondta hogy jön. ".repeat(2000);
+        let findings = analyzer(CombinationRule::NoisyOr).analyze(&text);
+
+        assert_eq!(findings.len(), 2000);
+    }
+
+    #[test]
+    fn ranked_by_probability() {
+        let findings = analyzer(CombinationRule::NoisyOr).analyze("Remélem jól vagy. Azt mondta hogy jön.");
+
+        assert_eq!(findings.len(), 2);
+        assert!((findings[0].prob - 0.9).abs() < 1e-9);
+        assert_eq!(findings[0].sources[0].0, "naive");
+        assert!((findings[1].prob - 0.3).abs() < 1e-9);
+        assert_eq!(findings[1].sources[0].0, "typical");
+    }
+
+    #[test]
+    fn lazy_findings() {
+        let text = "Azt mondta hogy jön. Remélem jól vagy. Ha mész vásárolni akkor hozz tejet!";
+        let mut analyzer = analyzer(CombinationRule::NoisyOr);
+        let findings = analyzer.analyze_iter(text).collect::<Vec<_>>();
+
+        assert_eq!(findings.len(), 3);
+        assert_eq!(findings.iter().map(|a| a.sources[0].0.as_str()).collect::<Vec<&str>>(), vec!["naive", "typical", "naive"]);
+        assert!((findings[2].prob - 0.8).abs() < 1e-9);
+        assert_eq!(findings[2].sources.len(), 2);
+
+        let probable = analyzer.analyze_iter(text).filter(|a| a.prob > 0.5).take(1).collect::<Vec<_>>();
+        assert_eq!(probable.len(), 1);
+        assert_eq!(probable[0].sources[0].1.rule_id, "before:hogy");
+    }
+
+    #[test]
+    fn combination_rules() {
+        assert!((CombinationRule::NoisyOr.combine(vec![0.5, 0.5]) - 0.75).abs() < 1e-9);
+        assert!((CombinationRule::Max.combine(vec![0.5, 0.2]) - 0.5).abs() < 1e-9);
+        assert_eq!(CombinationRule::NoisyOr.combine(Vec::new()), 0.0);
+    }
+
+    fn default_analyzer() -> Analyzer {
+        let mut analyzer = Analyzer::new(CombinationRule::NoisyOr);
+        analyzer.add_detector("before", Box::new(NaiveDetector::new(NaiveSettings::hungarian_default()).unwrap()));
+        analyzer.add_detector("after", Box::new(NaiveForwardDetector::new(NaiveSettings::hungarian_forward_default()).unwrap()));
+        analyzer.add_detector("pair", Box::new(PairDetector::new(PairSettings::hungarian_default()).unwrap()));
+        analyzer.add_detector("typical", Box::new(TypicalDetector::new(TypicalSettings::hungarian_default()).unwrap()));
+        analyzer.add_detector("superfluous", Box::new(SuperfluousDetector::new(SuperfluousSettings::hungarian_default()).unwrap()));
+
+        analyzer
+    }
+
+    #[test]
+    fn bundled_dictionaries_contain_readme_words() {
+        let before = NaiveSettings::hungarian_default();
+        let after = NaiveSettings::hungarian_forward_default();
+        let pair = PairSettings::hungarian_default();
+        let typical = TypicalSettings::hungarian_default();
+
+        for word in ["hogy", "ami", "aki"].iter() {
+            assert!(before.words.iter().any(|a| a == word));
+        }
+        for word in ["szia", "na"].iter() {
+            assert!(after.words.iter().any(|a| a == word));
+        }
+        let ha = pair.first_words.iter().position(|a| a == "ha").unwrap();
+        assert!(pair.second_words[ha].iter().any(|a| a == "akkor"));
+        assert!(typical.words.iter().any(|a| a == "reméljük"));
+    }
+
+    #[test]
+    fn bundled_dictionaries_correct_text() {
+        let findings = default_analyzer().analyze("Ha mész vásárolni, akkor ne felejts el tejet hozni! Na, mondd már, hogy sikerült! Reméljük, nem esett baja.");
+
+        assert_eq!(findings.len(), 0);
+    }
+
+    #[test]
+    fn bundled_dictionaries_detect_readme_examples() {
+        let findings = default_analyzer().analyze("Ha mész vásárolni akkor ne felejts el tejet hozni! Na mondd már hogy sikerült! Reméljük nem esett baja.");
+        let rule_ids = findings.iter().flat_map(|a| a.sources.iter().map(|(_, b)| b.rule_id.as_str())).collect::<Vec<&str>>();
+
+        assert!(rule_ids.contains(&"pair:ha:akkor"));
+        assert!(rule_ids.contains(&"after:na"));
+        assert!(rule_ids.contains(&"before:hogy"));
+        assert!(rule_ids.contains(&"typical:reméljük"));
+    }
+}
@@ -0,0 +1,3 @@
+mod combined;
+
+pub use combined::{Analyzer, FindingIter};
@@ -0,0 +1,73 @@
+use crate::model::{TokenKind, Position, Token};
+
+/// Keeps track of where the detector is in the text.
+///
+/// Converts the positions of the tokens of a row (given to detect_errors_in_row) into positions in
+/// the whole text, by counting the lines of the previous rows. Byte spans are left as they are,
+/// they are relative to the row.
+///
+/// It also remembers where the previous word (or punctuation) ended, since that is the place where
+/// a missing comma has to be inserted, and whether the current token starts a sentence or a quote.
+#[derive(Default)]
+pub(crate) struct Cursor {
+    previous_lines: usize,
+    last_line: usize,
+    previous_end: Option<Position>,
+    insertion_point: Option<Position>,
+    previous_opens_sentence: bool,
+    sentence_start: bool,
+}
+
+impl Cursor {
+    /// Moves the cursor to the given token and returns its position.
+    pub fn next(&mut self, token: &Token) -> Position {
+        self.last_line = token.position.line;
+
+        let position = Position {
+            line: self.previous_lines + token.position.line,
+            ..token.position.clone()
+        };
+
+        self.insertion_point = Some(self.previous_end.clone().unwrap_or(Position {
+            span: position.span.start..position.span.start,
+            ..position.clone()
+        }));
+        self.sentence_start = self.previous_end.is_none() || self.previous_opens_sentence;
+        if token.kind == TokenKind::ParagraphBreak {
+            self.previous_opens_sentence = true;
+        } else if token.kind != TokenKind::NewLine {
+            self.previous_opens_sentence = token.kind == TokenKind::EndOfSentence || token.kind == TokenKind::OpeningQuote;
+            self.previous_end = Some(Position {
+                span: position.span.end..position.span.end,
+                line: position.line,
+                col: position.col + token.text.chars().count(),
+                utf16_col: position.utf16_col + token.text.encode_utf16().count(),
+            });
+        }
+
+        position
+    }
+
+    /// The place right after the end of the last token before the current one, which is not a line
+    /// break. If the current token is the first one, it's the start of the current token.
+    pub fn insertion_point(&self) -> Position {
+        self.insertion_point.clone().unwrap_or_default()
+    }
+
+    /// The place right after the end of the current token (or the last one before it, if it is a
+    /// line break).
+    pub fn end_of_token(&self) -> Position {
+        self.previous_end.clone().unwrap_or_default()
+    }
+
+    /// True if the current token is the first one of the text, of a sentence or of a quote. Words
+    /// there never need a comma before them.
+    pub fn is_sentence_start(&self) -> bool {
+        self.sentence_start
+    }
+
+    pub fn end_of_row(&mut self) {
+        self.previous_lines += self.last_line.max(1);
+        self.last_line = 0;
+    }
+}
@@ -2,8 +2,22 @@ mod typical;
 mod pair;
 mod naive;
 mod naive_forward;
+mod superfluous;
+mod cursor;
+mod phrase_trie;
+mod verb;
 
 pub use naive::NaiveDetector;
 pub use naive_forward::NaiveForwardDetector;
 pub use pair::PairDetector;
-pub use typical::TypicalDetector;
\ No newline at end of file
+pub use superfluous::SuperfluousDetector;
+pub use typical::TypicalDetector;
+
+/// The NaiveDetector used by the tests of the modules which drive the detectors (streams, autofix),
+/// it knows "hogy" (0.9) and "akkor" (0.4).
+#[cfg(test)]
+pub(crate) fn naive_fixture() -> NaiveDetector {
+    use crate::model::NaiveSettings;
+
+    NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy"), String::from("akkor")], probs: vec![0.9, 0.4], exceptions: Vec::new() }).unwrap()
+}
@@ -1,13 +1,26 @@
-use crate::model::{PlainTextToken, Mistake, NaiveSettings};
-use logos::Lexer;
+use crate::model::{ContextException, ContextSide, TokenKind, DetectorKind, Mistake, Normalization, Phrase, NaiveSettings, Token, ValidationError};
 use crate::traits::Detector;
+use crate::detector::cursor::Cursor;
+use crate::detector::phrase_trie::{MatchedWord, PhraseMatches, PhraseTrie};
+
+/// A mistake whose entry has exceptions depending on the following word, so it can only be reported
+/// after the next token.
+struct PendingMistake {
+    pos: usize,
+    comma_at: usize,
+    word: MatchedWord,
+    prob: f64,
+}
 
-/// Contains the status of a NaiveDetector (row, column, is_last_token_comma, is_last_token_in_vec)
+/// Contains the status of a NaiveDetector (cursor, matches, pending, previous_word,
+/// is_last_token_comma, is_last_token_in_vec)
 ///
 /// Generally you shouldn't bother with it.
 struct NaiveStatus {
-    col: usize,
-    row: usize,
+    cursor: Cursor,
+    matches: PhraseMatches,
+    pending: Vec<PendingMistake>,
+    previous_word: Option<String>,
     is_last_token_comma: bool,
     is_last_token_in_vec: bool,
 }
@@ -15,8 +28,10 @@ struct NaiveStatus {
 impl NaiveStatus {
     pub fn new() -> NaiveStatus {
         NaiveStatus {
-            col: 1,
-            row: 1,
+            cursor: Cursor::default(),
+            matches: PhraseMatches::default(),
+            pending: Vec::new(),
+            previous_word: None,
             is_last_token_comma: false,
             is_last_token_in_vec: false,
         }
@@ -29,168 +44,491 @@ impl NaiveStatus {
 /// Every word is given a probability, which means the following: what is the probability of actually
 /// needing a comma before that word.
 ///
+/// The words can be phrases too (e.g. "annak ellenére, hogy"), these require a comma at the marked
+/// place, or before the phrase if there is no mark. Commas in the text don't break a phrase, the
+/// other punctuation does. Phrases whose comma is marked after their last word are left to the
+/// NaiveForwardDetector.
+///
 /// Exception: if there are two of the given words immediately after each other, the second one
-/// doesn't require a comma before it (the first one still does)
+/// doesn't require a comma before it (the first one still does). Words starting a sentence or a
+/// quote never require a comma before them, neither do words after a colon, a dash or an opening
+/// parenthesis. The settings can override the probability of a word
+/// depending on the word before or after it (see ContextException), nothing is reported if the
+/// probability is overridden to 0.
 pub struct NaiveDetector {
     settings: NaiveSettings,
+    normalization: Normalization,
+    trie: PhraseTrie,
     status: NaiveStatus,
 }
 
 impl NaiveDetector {
-    pub fn new(settings: NaiveSettings) -> NaiveDetector {
-        NaiveDetector {
+    /// Fails if the settings are not valid (see NaiveSettings::validate)
+    pub fn new(settings: NaiveSettings) -> Result<NaiveDetector, ValidationError> {
+        settings.validate()?;
+
+        let normalization = Normalization::default();
+        Ok(NaiveDetector {
+            trie: PhraseTrie::new(&settings.words, &normalization),
+            normalization,
             settings,
             status: NaiveStatus::new(),
-        }
+        })
+    }
+
+    /// Sets how the words are normalized before matching (lowercase by default).
+    pub fn with_normalization(mut self, normalization: Normalization) -> NaiveDetector {
+        self.trie = PhraseTrie::new(&self.settings.words, &normalization);
+        self.normalization = normalization;
+        self
+    }
+
+    /// True if the word is part of an other completed phrase, but not at the place of its comma (e.g.
+    /// "hogy" in "mivel hogy").
+    fn is_inside_other_phrase(&self, completed: &[(usize, Vec<MatchedWord>)], word: &MatchedWord) -> bool {
+        completed.iter().any(|(pos, words)| {
+            let comma_at = self.trie.phrase(*pos).comma_at.unwrap_or(0);
+            words.iter().enumerate().any(|(index, a)| index != comma_at && a.position.span == word.position.span)
+        })
     }
 
-    fn move_cursor_forward(&mut self, current_token: &PlainTextToken, tokens: &Lexer<PlainTextToken>) {
-        self.status.col += tokens.slice().chars().count() + 1;
-        if *current_token == PlainTextToken::NewLine {
-            self.status.col = 1;
-            self.status.row += 1;
+    fn context_prob(&self, pos: usize, side: ContextSide, word: Option<&str>, prob: f64) -> f64 {
+        ContextException::apply(self.settings.exceptions_of(pos), side, word, prob, |a| self.normalization.normalize(a))
+    }
+
+    /// Reports the pending mistakes, `next_word` is the word following them (None if they are
+    /// followed by punctuation or the end of the text).
+    fn resolve_pending(&mut self, next_word: Option<&str>, errors: &mut Vec<(usize, usize, Mistake)>) {
+        for pending in std::mem::take(&mut self.status.pending) {
+            let prob = self.context_prob(pending.pos, ContextSide::Following, next_word, pending.prob);
+            if prob > 0.0 {
+                errors.push(self.get_mistake_for_word(pending.pos, pending.comma_at, &pending.word, prob));
+            }
         }
     }
 
-    fn get_mistake_for_word(&self, pos: usize) -> (usize, usize, Mistake) {
+    fn get_mistake_for_word(&self, pos: usize, comma_at: usize, word: &MatchedWord, prob: f64) -> (usize, usize, Mistake) {
+        let phrase = &self.settings.words[pos];
+        let msg = if self.trie.phrase(pos).is_single_word() {
+            format!("a(z) \"{}\" szó elé általában vesszőt teszünk.", phrase)
+        } else {
+            let words = Phrase::parse(phrase).expect("the phrases are validated").words;
+            format!("a(z) \"{}\" kifejezésben általában vesszőt teszünk a(z) \"{}\" szó elé.", phrase, words[comma_at])
+        };
+
         (
-            self.status.row,
-            self.status.col,
-            Mistake::new_dyn(
-                format!("a(z) \"{}\" szó elé általában vesszőt teszünk.", self.settings.words[pos]),
-                self.settings.probs[pos]
-            )
+            word.position.line,
+            word.position.col,
+            Mistake::new_dyn(msg, prob)
+                .with_position(word.position.clone())
+                .with_rule(DetectorKind::BeforeWord, &[phrase])
+                .with_insertion_point(word.insertion_point.clone())
         )
     }
 }
 
 impl Detector for NaiveDetector {
-    fn detect_errors(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)> {
+    fn reset(&mut self) {
         self.status = NaiveStatus::new();
-
-        self.detect_errors_in_row(tokens)
     }
 
-    fn detect_errors_in_row(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)> {
-        let mut errors = Vec::new();
-        while let Some(token) = tokens.next() {
-            let index = self.settings.words.iter().position(|a| a == tokens.slice());
-
-            if !self.status.is_last_token_comma && !self.status.is_last_token_in_vec {
-                if let Some(pos) = index {
-                    errors.push(self.get_mistake_for_word(pos));
+    fn next_token(&mut self, token: &Token, errors: &mut Vec<(usize, usize, Mistake)>) {
+        let position = self.status.cursor.next(token);
+
+        match token.kind {
+            TokenKind::NewLine => return,
+            kind if kind.is_word() => {
+                let text = self.normalization.normalize(token.text);
+                self.resolve_pending(Some(&text), errors);
+
+                let word = MatchedWord {
+                    insertion_point: self.status.cursor.insertion_point(),
+                    position,
+                    comma_not_needed: self.status.is_last_token_comma || self.status.cursor.is_sentence_start(),
+                    after_match: self.status.is_last_token_in_vec,
+                    previous_word: self.status.previous_word.take(),
+                };
+                let completed = self.status.matches.next(&self.trie, &text, word);
+
+                for (pos, words) in completed.iter() {
+                    let comma_at = self.trie.phrase(*pos).comma_at.unwrap_or(0);
+                    if let Some(word) = words.get(comma_at) {
+                        let is_exception = word.comma_not_needed
+                            || (comma_at == 0 && word.after_match)
+                            || self.is_inside_other_phrase(&completed, word);
+                        if is_exception {
+                            continue;
+                        }
+
+                        let prob = self.context_prob(*pos, ContextSide::Preceding, words[0].previous_word.as_deref(), self.settings.probs[*pos]);
+                        if self.settings.exceptions_of(*pos).iter().any(|a| a.side == ContextSide::Following) {
+                            self.status.pending.push(PendingMistake { pos: *pos, comma_at, word: word.clone(), prob });
+                        } else if prob > 0.0 {
+                            errors.push(self.get_mistake_for_word(*pos, comma_at, word, prob));
+                        }
+                    }
                 }
+                self.status.is_last_token_in_vec = !completed.is_empty();
+                self.status.previous_word = Some(text);
             }
-
-            self.move_cursor_forward(&token, tokens);
-
-            self.status.is_last_token_in_vec = index.is_some();
-            if token != PlainTextToken::NewLine {
-                self.status.is_last_token_comma = token == PlainTextToken::Comma;
+            TokenKind::Comma => {
+                self.resolve_pending(None, errors);
+                self.status.is_last_token_in_vec = false;
+                self.status.previous_word = None;
+            }
+            _ => {
+                self.resolve_pending(None, errors);
+                self.status.matches.clear();
+                self.status.is_last_token_in_vec = false;
+                self.status.previous_word = None;
             }
         }
 
-        self.status.row += 1;
+        self.status.is_last_token_comma = token.kind.replaces_comma();
+    }
+
+    fn end_of_row(&mut self, _errors: &mut Vec<(usize, usize, Mistake)>) {
+        self.status.cursor.end_of_row();
+    }
 
-        errors
+    fn end_of_text(&mut self, errors: &mut Vec<(usize, usize, Mistake)>) {
+        self.resolve_pending(None, errors);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use logos::Logos;
-
     use crate::detector::NaiveDetector;
-    use crate::model::{PlainTextToken, NaiveSettings};
+    use crate::model::{CaseMode, ContextException, ContextSide, DetectorKind, NaiveSettings, Normalization, Position, SuffixClass, TokenStream, ValidationError};
     use crate::traits::Detector;
 
     #[test]
     fn empty_str() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: Vec::new(), probs: Vec::new() });
-        let mut tokens = PlainTextToken::lexer("");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: Vec::new(), probs: Vec::new(), exceptions: Vec::new() }).unwrap();
+        let text = "";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn no_comma_in_sight() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy"), String::from("ha")], probs: vec![1.0, 1.0] });
-        let mut tokens = PlainTextToken::lexer("Ki kopog? Mi kopog? Egy fekete holló!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy"), String::from("ha")], probs: vec![1.0, 1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Ki kopog? Mi kopog? Egy fekete holló!";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn comma_provided() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Azt szeretném mondani, hogy minden jól sikerült.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Azt szeretném mondani, hogy minden jól sikerült.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn semicolon_provided() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Azt szeretném mondani; hogy minden jól sikerült.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Azt szeretném mondani; hogy minden jól sikerült.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn comma_duplicate_words() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Nem értem, hogy hogy kellene ezt csinálni.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Nem értem, hogy hogy kellene ezt csinálni.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn no_comma_one_line() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Azt szeretném mondani hogy minden jól sikerült.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Azt szeretném mondani hogy minden jól sikerült.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 1);
     }
 
     #[test]
     fn no_comma_next_line() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Azt szeretném mondani\nhogy minden jól sikerült.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Azt szeretném mondani\nhogy minden jól sikerült.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 1);
     }
 
     #[test]
-    fn no_comma_multiple_lines() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Azt szeretném mondani\n\n\n\nhogy minden jól sikerült.");
-        let errors = sut.detect_errors(&mut tokens);
+    fn spans_of_rows() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Azt mondta\nhogy jön.";
+        let whole = sut.detect_errors_in_stream(&TokenStream::new(text));
 
-        assert_eq!(errors.len(), 1);
+        assert_eq!(&text[whole[0].2.position.span.clone()], "hogy");
+        assert_eq!(whole[0].2.position.line, 2);
+
+        sut.reset();
+        sut.detect_errors_in_row("Azt mondta");
+        let rows = sut.detect_errors_in_row("hogy jön.");
+
+        assert_eq!(rows[0].2.position.line, 2);
+        assert_eq!(rows[0].2.position.span, 0..4);
+    }
+
+    #[test]
+    fn no_comma_across_paragraphs() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Azt szeretném mondani\n\n\n\nhogy minden jól sikerült.";
+        let errors = sut.detect_errors(text);
+
+        // The empty lines end the paragraph, and with it the sentence
+        assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn no_comma_duplicate_words() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Nem értem hogy hogy kellene ezt csinálni.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Nem értem hogy hogy kellene ezt csinálni.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 1);
     }
 
     #[test]
     fn no_comma_multiple_error_one_line() {
-        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy"), String::from("ha")], probs: vec![1.0, 1.0] });
-        let mut tokens = PlainTextToken::lexer("Nem értem hogy kellene ezt csinálni. Elmagyarázod ha szépen megkérlek?");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy"), String::from("ha")], probs: vec![1.0, 1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Nem értem hogy kellene ezt csinálni. Elmagyarázod ha szépen megkérlek?";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 2);
+    }
+
+    #[test]
+    fn no_comma_double_acute_word() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("mielőtt")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Mosd meg a kezed mielőtt eszel.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn stream_same_as_text() {
+        let text = "Nem értem hogy kellene ezt csinálni.\nElmagyarázod ha szépen megkérlek?";
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy"), String::from("ha")], probs: vec![1.0, 0.5], exceptions: Vec::new() }).unwrap();
+        let from_text = sut.detect_errors(text);
+        let from_stream = sut.detect_errors_in_stream(&TokenStream::new(text));
+
+        assert_eq!(from_text.len(), 2);
+        assert_eq!(from_text.iter().map(|(row, col, _)| (*row, *col)).collect::<Vec<_>>(), from_stream.iter().map(|(row, col, _)| (*row, *col)).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn exact_positions() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Azt  szeretném\tmondani # hogy\nő azt  hitte hogy";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].0, errors[0].1), (1, 26));
+        assert_eq!(errors[0].2.position, Position { span: 26..30, line: 1, col: 26, utf16_col: 26 });
+        assert_eq!((errors[1].0, errors[1].1), (2, 14));
+        assert_eq!(errors[1].2.position.span, 45..49);
+    }
+
+    #[test]
+    fn positions_in_rows() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let mut errors = sut.detect_errors_in_row("Nem értem");
+        errors.append(&mut sut.detect_errors_in_row(""));
+        errors.append(&mut sut.detect_errors_in_row("én sem  hogy miért"));
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].0, errors[0].1), (3, 9));
+        assert_eq!(errors[0].2.position.span, 9..13);
+    }
+
+    #[test]
+    fn inflected_trigger_words() {
+        let mut settings = NaiveSettings { words: Vec::new(), probs: Vec::new(), exceptions: Vec::new() };
+        settings.add_inflected("ami", 1.0, SuffixClass::Case);
+        settings.add_inflected("amelyik", 1.0, SuffixClass::Case);
+        let mut sut = NaiveDetector::new(settings).unwrap();
+        let text = "Nem tudom amiről beszélsz. Elvitte amit kért. Megvan amelyikről szóltál.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors[2].2.rule_id, "before:amelyikről");
+    }
+
+    #[test]
+    fn structured_mistake() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![0.9], exceptions: Vec::new() }).unwrap();
+        let text = "Azt mondta\n  hogy jön.";
+        let errors = sut.detect_errors(text);
+        let mistake = &errors[0].2;
+
+        assert_eq!(mistake.kind, DetectorKind::BeforeWord);
+        assert_eq!(mistake.rule_id, "before:hogy");
+        assert_eq!(mistake.triggers, vec![String::from("hogy")]);
+        assert_eq!(mistake.insert_at, Some(Position { span: 10..10, line: 1, col: 11, utf16_col: 11 }));
+        assert_eq!(mistake.get_str(), "a(z) \"hogy\" szó elé általában vesszőt teszünk.");
+    }
+
+    #[test]
+    fn invalid_probability_rejected() {
+        let sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.2], exceptions: Vec::new() });
+
+        assert_eq!(sut.err(), Some(ValidationError::ProbabilityOutOfRange { index: 0, prob: 1.2 }));
+    }
+
+    #[test]
+    fn uppercase_words() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("ami"), String::from("aki")], probs: vec![1.0, 1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Az a fiú AKI ott áll. Megvan Ami kell.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].2.rule_id, "before:aki");
+    }
+
+    #[test]
+    fn preserve_case() {
+        let settings = NaiveSettings { words: vec![String::from("aki")], probs: vec![1.0], exceptions: Vec::new() };
+        let mut sut = NaiveDetector::new(settings).unwrap().with_normalization(Normalization::new(CaseMode::Preserve, false));
+        let text = "Az a fiú AKI ott áll, és az aki ül.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.position.span, 31..34);
+    }
+
+    #[test]
+    fn fold_diacritics() {
+        let settings = NaiveSettings { words: vec![String::from("mielőtt")], probs: vec![1.0], exceptions: Vec::new() };
+        let mut sut = NaiveDetector::new(settings).unwrap().with_normalization(Normalization::new(CaseMode::Lowercase, true));
+        let text = "Mosd meg a kezed mielott eszel.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.rule_id, "before:mielőtt");
+    }
+
+    #[test]
+    fn sentence_start() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy"), String::from("ha")], probs: vec![1.0, 1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Hogy vagy? Ha ráérsz, gyere át!
+Ha nem, majd holnap.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn quote_start() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Azt kérdezte: „Hogy vagy?” Aztán \"hogy vagytok?\"";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn punctuation_instead_of_comma() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("aki"), String::from("hogy")], probs: vec![1.0, 1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Péter – aki a bátyám – jön. Péter (aki a bátyám) jön. Annyit mondott: hogy jön. Péter... aki jön.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.position.span.start, text.rfind("aki").unwrap());
+    }
+
+    #[test]
+    fn phrase_comma_marked() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("annak ellenére, hogy")], probs: vec![0.9], exceptions: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("Eljött annak ellenére hogy beteg volt. Eljött annak ellenére, hogy beteg volt.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.rule_id, "before:annak ellenére, hogy");
+        assert_eq!(errors[0].2.position.span, 24..28);
+        assert_eq!(errors[0].2.insert_at.as_ref().map(|a| a.span.clone()), Some(23..23));
+        assert_eq!(errors[0].2.get_str(), "a(z) \"annak ellenére, hogy\" kifejezésben általában vesszőt teszünk a(z) \"hogy\" szó elé.");
+    }
+
+    #[test]
+    fn phrase_without_mark() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("mivel hogy"), String::from("hogy")], probs: vec![0.9, 0.8], exceptions: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("Nem jött el mivel hogy beteg volt.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.rule_id, "before:mivel hogy");
+        assert_eq!(errors[0].2.position.span, 13..18);
+    }
+
+    #[test]
+    fn phrase_broken_by_punctuation() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("még akkor is, ha")], probs: vec![0.9], exceptions: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("Megyek még akkor is. Ha esik, akkor is.");
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn many_phrases() {
+        let letters = "abcdefghijklmnopqrstuvwxyz";
+        let mut words = letters.chars()
+            .flat_map(|a| letters.chars().map(move |b| format!("sz{}{} még, hogy", a, b)))
+            .flat_map(|a| vec![a.clone(), a.replace("még", "is"), a.replace("még", "ma"), a.replace("még", "se")])
+            .collect::<Vec<String>>();
+        words.push(String::from("azért, mert"));
+        let probs = vec![0.5; words.len()];
+        let mut sut = NaiveDetector::new(NaiveSettings { words, probs, exceptions: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("Sírt azért mert fájt, mondta még hogy szxy még hogy jön.");
+
+        assert_eq!(errors.iter().map(|(_, _, a)| a.rule_id.as_str()).collect::<Vec<&str>>(), vec!["before:azért, mert", "before:szxy még, hogy"]);
+    }
+
+    #[test]
+    fn preceding_context() {
+        let exceptions = vec![vec![ContextException::new(ContextSide::Preceding, "csak", 0.0), ContextException::new(ContextSide::Preceding, "még", 0.2)]];
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("ha")], probs: vec![0.6], exceptions }).unwrap();
+        let errors = sut.detect_errors("Eljövök csak ha hívsz. Eljövök még ha esik is. Eljövök ha hívsz.");
+
+        assert_eq!(errors.iter().map(|(_, _, a)| a.prob).collect::<Vec<f64>>(), vec![0.2, 0.6]);
+    }
+
+    #[test]
+    fn following_context() {
+        let exceptions = vec![vec![ContextException::new(ContextSide::Following, "nem", 0.1)]];
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hacsak")], probs: vec![0.9], exceptions }).unwrap();
+        let errors = sut.detect_errors("Eljövök hacsak nem esik. Eljövök hacsak\nlehet. Eljövök hacsak");
+
+        assert_eq!(errors.iter().map(|(_, _, a)| a.prob).collect::<Vec<f64>>(), vec![0.1, 0.9, 0.9]);
+        assert_eq!(errors.iter().map(|(row, _, _)| *row).collect::<Vec<usize>>(), vec![1, 1, 2]);
+    }
+
+    #[test]
+    fn following_context_over_rows() {
+        let exceptions = vec![vec![ContextException::new(ContextSide::Following, "nem", 0.1)]];
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hacsak")], probs: vec![0.9], exceptions }).unwrap();
+        let mut errors = sut.detect_errors_in_row("Eljövök hacsak");
+        errors.append(&mut sut.detect_errors_in_row("nem esik."));
+        sut.end_of_text(&mut errors);
+
+        assert_eq!(errors.iter().map(|(_, _, a)| a.prob).collect::<Vec<f64>>(), vec![0.1]);
+    }
+
+    #[test]
+    fn mismatched_exceptions_rejected() {
+        let sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("ha"), String::from("hogy")], probs: vec![0.5, 0.9], exceptions: vec![Vec::new()] });
+
+        assert_eq!(sut.err(), Some(ValidationError::LengthMismatch { field: "exceptions", expected: 2, found: 1 }));
     }
 }
@@ -1,21 +1,29 @@
-use crate::model::{PlainTextToken, Mistake, NaiveSettings};
-use logos::Lexer;
+use crate::model::{ContextException, ContextSide, TokenKind, DetectorKind, Mistake, Normalization, Phrase, Position, NaiveSettings, Token, ValidationError};
 use crate::traits::Detector;
+use crate::detector::cursor::Cursor;
+use crate::detector::phrase_trie::{MatchedWord, PhraseMatches, PhraseTrie};
 
-/// Contains the status of a NaiveForwardDetector (row, column, active_word)
+/// Contains the status of a NaiveForwardDetector (cursor, matches, previous_word,
+/// is_last_token_comma, active_word)
 ///
 /// Generally you shouldn't bother with it.
 struct NaiveForwardStatus {
-    col: usize,
-    row: usize,
-    active_word: Option<usize>
+    cursor: Cursor,
+    matches: PhraseMatches,
+    previous_word: Option<String>,
+    is_last_token_comma: bool,
+    /// The index of the word whose comma is missing if the next token is a word, and its
+    /// probability in the context of the preceding word.
+    active_word: Option<(usize, f64)>
 }
 
 impl NaiveForwardStatus {
     pub fn new() -> NaiveForwardStatus {
         NaiveForwardStatus {
-            col: 1,
-            row: 1,
+            cursor: Cursor::default(),
+            matches: PhraseMatches::default(),
+            previous_word: None,
+            is_last_token_comma: false,
             active_word: None,
         }
     }
@@ -27,140 +35,245 @@ impl NaiveForwardStatus {
 /// Every word is given a probability, which means the following: what is the probability of actually
 /// needing a comma after that word.
 ///
+/// The words can be phrases too, these require a comma after the phrase, or at the marked place if
+/// the comma is marked inside the phrase.
+///
+/// The settings can override the probability of a word depending on the word before or after it
+/// (see ContextException), nothing is reported if the probability is overridden to 0.
+///
 /// Example: greetings that are not adjectives: szia, helló; but not: "kedves" "tisztelt"
-
 pub struct NaiveForwardDetector {
     settings: NaiveSettings,
+    normalization: Normalization,
+    trie: PhraseTrie,
     status: NaiveForwardStatus,
 }
 
 impl NaiveForwardDetector {
-    /// Since they are really similar, the NaiveForwardDetector uses the same
-    pub fn new(settings: NaiveSettings) -> NaiveForwardDetector {
-        NaiveForwardDetector {
+    /// Since they are really similar, the NaiveForwardDetector uses the same settings as the
+    /// NaiveDetector. Fails if the settings are not valid (see NaiveSettings::validate)
+    pub fn new(settings: NaiveSettings) -> Result<NaiveForwardDetector, ValidationError> {
+        settings.validate()?;
+
+        let normalization = Normalization::default();
+        Ok(NaiveForwardDetector {
+            trie: PhraseTrie::new(&settings.words, &normalization),
+            normalization,
             settings,
             status: NaiveForwardStatus::new(),
-        }
+        })
     }
 
-    fn move_cursor_forward(&mut self, current_token: &PlainTextToken, tokens: &Lexer<PlainTextToken>) {
-        self.status.col += tokens.slice().chars().count() + 1;
-        if *current_token == PlainTextToken::NewLine {
-            self.status.col = 1;
-            self.status.row += 1;
-        }
+    /// Sets how the words are normalized before matching (lowercase by default).
+    pub fn with_normalization(mut self, normalization: Normalization) -> NaiveForwardDetector {
+        self.trie = PhraseTrie::new(&self.settings.words, &normalization);
+        self.normalization = normalization;
+        self
     }
 
-    fn get_mistake_for_word(&self, pos: usize) -> (usize, usize, Mistake) {
+    fn context_prob(&self, pos: usize, side: ContextSide, word: Option<&str>, prob: f64) -> f64 {
+        ContextException::apply(self.settings.exceptions_of(pos), side, word, prob, |a| self.normalization.normalize(a))
+    }
+
+    fn get_mistake_for_word(&self, pos: usize, position: Position, prob: f64) -> (usize, usize, Mistake) {
         (
-            self.status.row,
-            self.status.col,
+            position.line,
+            position.col,
             Mistake::new_dyn(
                 format!("a(z) \"{}\" szó után általában vesszőt teszünk.", self.settings.words[pos]),
-                self.settings.probs[pos]
+                prob
             )
+                .with_position(position)
+                .with_rule(DetectorKind::AfterWord, &[&self.settings.words[pos]])
+                .with_insertion_point(self.status.cursor.insertion_point())
         )
     }
 
-    fn is_token_word(&self, token: &PlainTextToken) -> bool {
-        *token == PlainTextToken::Number || *token == PlainTextToken::Text
+    fn get_mistake_inside_phrase(&self, pos: usize, comma_at: usize, word: &MatchedWord, prob: f64) -> (usize, usize, Mistake) {
+        let phrase = &self.settings.words[pos];
+        let words = Phrase::parse(phrase).expect("the phrases are validated").words;
+
+        (
+            word.position.line,
+            word.position.col,
+            Mistake::new_dyn(
+                format!("a(z) \"{}\" kifejezésben általában vesszőt teszünk a(z) \"{}\" szó után.", phrase, words[comma_at - 1]),
+                prob
+            )
+                .with_position(word.position.clone())
+                .with_rule(DetectorKind::AfterWord, &[phrase])
+                .with_insertion_point(word.insertion_point.clone())
+        )
     }
 }
 
 impl Detector for NaiveForwardDetector {
-
-    fn detect_errors(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)> {
+    fn reset(&mut self) {
         self.status = NaiveForwardStatus::new();
-
-        self.detect_errors_in_row(tokens)
     }
 
-    fn detect_errors_in_row(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)> {
-        let mut errors = Vec::new();
+    fn next_token(&mut self, token: &Token, errors: &mut Vec<(usize, usize, Mistake)>) {
+        let position = self.status.cursor.next(token);
+        let text = self.normalization.normalize(token.text);
 
-        while let Some(token) = tokens.next() {
-            let lowercase = String::from(tokens.slice()).to_lowercase();
-            let index = self.settings.words.iter().position(|a| a == lowercase.as_str());
-
-            if let Some(pos) = self.status.active_word {
-                if self.is_token_word(&token) {
-                    errors.push(self.get_mistake_for_word(pos));
+        if let Some((pos, prob)) = self.status.active_word {
+            if token.kind.is_word() {
+                let prob = self.context_prob(pos, ContextSide::Following, Some(&text), prob);
+                if prob > 0.0 {
+                    errors.push(self.get_mistake_for_word(pos, position.clone(), prob));
                 }
             }
+        }
 
-            self.move_cursor_forward(&token, tokens);
-
-            if token != PlainTextToken::NewLine {
-                self.status.active_word = index;
+        if token.kind == TokenKind::NewLine {
+            return;
+        }
+        self.status.active_word = None;
+
+        if token.kind.is_word() {
+            let word = MatchedWord {
+                insertion_point: self.status.cursor.insertion_point(),
+                position,
+                comma_not_needed: self.status.is_last_token_comma,
+                after_match: false,
+                previous_word: self.status.previous_word.take(),
+            };
+            let completed = self.status.matches.next(&self.trie, &text, word);
+
+            for (pos, words) in completed.iter() {
+                let prob = self.context_prob(*pos, ContextSide::Preceding, words[0].previous_word.as_deref(), self.settings.probs[*pos]);
+                match self.trie.phrase(*pos).comma_at {
+                    Some(comma_at) if comma_at < words.len() => {
+                        if comma_at > 0 && !words[comma_at].comma_not_needed && prob > 0.0 {
+                            errors.push(self.get_mistake_inside_phrase(*pos, comma_at, &words[comma_at], prob));
+                        }
+                    }
+                    _ => self.status.active_word = Some((*pos, prob)),
+                }
+            }
+            self.status.previous_word = Some(text);
+        } else {
+            if token.kind != TokenKind::Comma {
+                self.status.matches.clear();
             }
+            self.status.previous_word = None;
         }
 
-        self.status.row += 1;
+        self.status.is_last_token_comma = token.kind.replaces_comma();
+    }
 
-        errors
+    fn end_of_row(&mut self, _errors: &mut Vec<(usize, usize, Mistake)>) {
+        self.status.cursor.end_of_row();
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use logos::Logos;
-
     use crate::detector::NaiveForwardDetector;
-    use crate::model::{PlainTextToken, NaiveSettings};
+    use crate::model::{ContextException, ContextSide, DetectorKind, NaiveSettings};
     use crate::traits::Detector;
 
     #[test]
     fn empty_str() {
-        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn no_comma_in_sight() {
-        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Mi van?");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Mi van?";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn no_comma_required_if_terminated() {
-        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Szia!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Szia!";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn comma_provided() {
-        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Szia, meghoztuk a tudod... Hmmm...");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Szia, meghoztuk a tudod... Hmmm...";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn semicolon_provided() {
-        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Szia; meghoztuk a tudod... Hmmm...");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Szia; meghoztuk a tudod... Hmmm...";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn comma_missing() {
-        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0] });
-        let mut tokens = PlainTextToken::lexer("Szia meghoztuk a tudod... Hmmm...");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Szia meghoztuk a tudod... Hmmm...";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 1);
     }
 
+    #[test]
+    fn structured_mistake() {
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("szia")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Szia meghoztuk.";
+        let errors = sut.detect_errors(text);
+        let mistake = &errors[0].2;
+
+        assert_eq!(mistake.kind, DetectorKind::AfterWord);
+        assert_eq!(mistake.rule_id, "after:szia");
+        assert_eq!(mistake.insert_at.as_ref().map(|a| a.span.clone()), Some(4..4));
+    }
+
+    #[test]
+    fn comma_missing_double_acute_word() {
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("őszintén")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let text = "Őszintén nem tudom.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn phrase_comma_missing() {
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("hát persze")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("Hát persze jövök. Hát persze, megyek. Hát jövök.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.rule_id, "after:hát persze");
+        assert_eq!(errors[0].2.position.span, 12..19);
+    }
+
+    #[test]
+    fn phrase_comma_marked_inside() {
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("azért, mert")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("Sírt azért mert fájt. Nevetett azért, mert jó volt.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.get_str(), "a(z) \"azért, mert\" kifejezésben általában vesszőt teszünk a(z) \"azért\" szó után.");
+    }
+
+    #[test]
+    fn context_exceptions() {
+        let exceptions = vec![vec![ContextException::new(ContextSide::Following, "meg", 0.0), ContextException::new(ContextSide::Preceding, "úgy", 0.0)]];
+        let mut sut = NaiveForwardDetector::new(NaiveSettings { words: vec![String::from("na")], probs: vec![0.8], exceptions }).unwrap();
+        let errors = sut.detect_errors("Na meg ilyet. Úgy na lesz. Na gyere.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.position.span, 31..36);
+    }
 }
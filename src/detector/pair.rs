@@ -1,23 +1,50 @@
-use crate::model::{PlainTextToken, Mistake, PairSettings};
-use logos::Lexer;
-use std::cell::Cell;
+use crate::model::{TokenKind, CommaAction, DetectorKind, Mistake, Normalization, PairSettings, Position, Token, ValidationError};
 use crate::traits::Detector;
+use crate::detector::cursor::Cursor;
+use crate::detector::phrase_trie::{MatchedWord, PhraseMatches, PhraseTrie};
+
+/// The place of an anchor in the settings: the index of the pair, the index of the part (0 is the
+/// first word, 1 are the second words, the rest are the further parts) and the index of the
+/// alternative inside the part.
+#[derive(Clone, Copy, PartialEq)]
+struct AnchorRole {
+    index: usize,
+    part: usize,
+    alternative: usize,
+}
+
+/// A pair whose first word already appeared, its next part is looked for.
+struct ActivePair {
+    index: usize,
+    /// The index of the second word, if it appeared first (see PairOptions::reversible), so the
+    /// first word is looked for.
+    reversed: Option<usize>,
+    /// The index of the part that is looked for.
+    next_part: usize,
+    /// The number of tokens since the previous part.
+    distance: usize,
+    /// The positions of the commas since the previous part, except the ones right before an anchor
+    /// of the pair which needs a comma (those are in their right place, e.g. before a nested pair).
+    commas: Vec<Position>,
+}
 
-/// Contains the status of a PairDetector (row, column, first_word_active)
+/// Contains the status of a PairDetector (cursor, matches, active pairs, is_last_token_comma)
 ///
 /// Generally you shouldn't bother with it.
 struct PairStatus {
-    pub col: usize,
-    pub row: usize,
-    pub first_word_active: Vec<Cell<bool>>,
+    pub cursor: Cursor,
+    pub matches: PhraseMatches,
+    pub active: Vec<ActivePair>,
+    pub is_last_token_comma: bool,
 }
 
 impl PairStatus {
-    pub fn new(words_len: usize) -> PairStatus {
+    pub fn new() -> PairStatus {
         PairStatus {
-            col: 1,
-            row: 1,
-            first_word_active: vec![Cell::new(false); words_len],
+            cursor: Cursor::default(),
+            matches: PhraseMatches::default(),
+            active: Vec::new(),
+            is_last_token_comma: false,
         }
     }
 }
@@ -25,168 +52,534 @@ impl PairStatus {
 /// Detects pairs of words which usually require a comma between them, these words don't have to be
 /// right next to each other! Each pair is given a probability, just like in the NaiveDetector.
 ///
-/// All input will be converted to lowercase!
+/// The words are normalized before matching (lowercase by default, see with_normalization). A
+/// second word starting a quote doesn't require a comma before it.
 ///
-/// This doesn't detect if the comma is in the wrong place between the two words! (Not sure yet)
+/// The comma is expected right before the second word. If there is a comma between the two words,
+/// but somewhere else, it is reported as a misplaced comma (unless that comma is right before an
+/// other part of the pair, e.g. of a nested pair, where it belongs anyway): besides the missing
+/// comma, a single stray comma is reported to be removed. The pairs are looked for until the end
+/// of the sentence, or until the maximal distance of the pair; the order of the words and nesting
+/// are controlled by the PairOptions of the pair.
+///
+/// The words of a pair can be anchors of more words ("nem csak"), and a pair can have more than two
+/// parts ("nemcsak ... hanem ... is"), every part is checked the same way as the second word. The
+/// mistakes point at the first word of the anchor that needs a comma before it. Reversed pairs only
+/// have two parts.
 ///
 /// Example: ha ... akkor: Ha mész vásárolni, akkor ne felejts el tejet hozni!
 /// (If you go shopping don't forget to buy milk!)
-///
-///
 pub struct PairDetector {
     settings: PairSettings,
+    normalization: Normalization,
+    roles: Vec<AnchorRole>,
+    trie: PhraseTrie,
     status: PairStatus,
 }
 
 impl Detector for PairDetector {
-    fn detect_errors(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)> {
-        self.status = PairStatus::new(self.settings.first_words.len());
-
-        self.detect_errors_in_row(tokens)
+    fn reset(&mut self) {
+        self.status = PairStatus::new();
     }
 
-    fn detect_errors_in_row(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)> {
-        let mut errors = Vec::new();
-        while let Some(token) = tokens.next() {
-            let lowercase = String::from(tokens.slice()).to_lowercase();
-
-            // Checks for every active (which already appeared) first word if any of it's second words
-            // is the current one. If it is, then there might be a missing comma.
-            for (index, active) in self.status.first_word_active.iter().enumerate() {
-                if active.get() {
-                    let second_index = self.settings.second_words[index].iter().position(|a| a == lowercase.as_str());
-                    if let Some(pos) = second_index {
-                        errors.push(self.get_mistake_for_word(index, pos));
-                    }
+    fn next_token(&mut self, token: &Token, errors: &mut Vec<(usize, usize, Mistake)>) {
+        let position = self.status.cursor.next(token);
+
+        match token.kind {
+            TokenKind::NewLine => return,
+            kind if kind.is_word() => {
+                let word = MatchedWord {
+                    insertion_point: self.status.cursor.insertion_point(),
+                    position,
+                    comma_not_needed: self.status.is_last_token_comma || self.status.cursor.is_sentence_start(),
+                    after_match: false,
+                    previous_word: None,
+                };
+                let text = self.normalization.normalize(token.text);
+                self.advance();
+
+                let completed = self.status.matches.next(&self.trie, &text, word);
+                let mut roles = Vec::new();
+                for (anchor, words) in completed.iter() {
+                    roles.push((self.roles[*anchor], &words[0]));
                 }
-            }
 
-            self.move_cursor_forward(&token, tokens);
-            self.set_active_word(lowercase.as_str());
-
-            // If it's a comma or a new sentence, then we don't need to check anymore if it is missing between words.
-            if token == PlainTextToken::Comma || token == PlainTextToken::EndOfSentence {
-                self.status.first_word_active.iter().for_each(|a| a.set(false));
+                self.keep_misplaced_commas(&roles);
+                let handled = self.continue_pairs(&roles, errors);
+                self.open_pairs(&roles, &handled);
+            }
+            TokenKind::Comma => {
+                self.advance();
+                self.status.active.iter_mut().for_each(|a| a.commas.push(position.clone()));
+            }
+            // If it's a new sentence (or paragraph), then we don't need to check anymore if it is missing between words.
+            kind if kind.ends_sentence() => {
+                self.status.matches.clear();
+                self.status.active.clear();
+            }
+            _ => {
+                self.status.matches.clear();
+                self.advance();
             }
         }
 
-        self.status.row += 1;
+        self.status.is_last_token_comma = token.kind.replaces_comma();
+    }
 
-        errors
+    fn end_of_row(&mut self, _errors: &mut Vec<(usize, usize, Mistake)>) {
+        self.status.cursor.end_of_row();
     }
 }
 
 impl PairDetector {
-    pub fn new(settings: PairSettings) -> PairDetector {
-        PairDetector {
-            status: PairStatus::new(settings.first_words.len()),
+    /// Fails if the settings are not valid (see PairSettings::validate)
+    pub fn new(settings: PairSettings) -> Result<PairDetector, ValidationError> {
+        settings.validate()?;
+
+        let (anchors, roles) = PairDetector::anchors(&settings);
+        let normalization = Normalization::default();
+        Ok(PairDetector {
+            status: PairStatus::new(),
+            trie: PhraseTrie::new(&anchors, &normalization),
+            roles,
+            normalization,
             settings,
+        })
+    }
+
+    /// Sets how the words are normalized before matching (lowercase by default).
+    pub fn with_normalization(mut self, normalization: Normalization) -> PairDetector {
+        let (anchors, _) = PairDetector::anchors(&self.settings);
+        self.trie = PhraseTrie::new(&anchors, &normalization);
+        self.normalization = normalization;
+        self
+    }
+
+    /// Every anchor of every part of the pairs, with their role.
+    fn anchors(settings: &PairSettings) -> (Vec<String>, Vec<AnchorRole>) {
+        let mut anchors = Vec::new();
+        let mut roles = Vec::new();
+
+        for index in 0..settings.first_words.len() {
+            let parts = std::iter::once(std::slice::from_ref(&settings.first_words[index]))
+                .chain(std::iter::once(settings.second_words[index].as_slice()))
+                .chain(settings.further_parts_of(index).iter().map(|a| a.words.as_slice()));
+
+            for (part, words) in parts.enumerate() {
+                for (alternative, word) in words.iter().enumerate() {
+                    anchors.push(word.clone());
+                    roles.push(AnchorRole { index, part, alternative });
+                }
+            }
+        }
+
+        (anchors, roles)
+    }
+
+    fn part_count(&self, index: usize) -> usize {
+        2 + self.settings.further_parts_of(index).len()
+    }
+
+    fn anchor_text(&self, index: usize, part: usize, alternative: usize) -> &str {
+        match part {
+            0 => &self.settings.first_words[index],
+            1 => &self.settings.second_words[index][alternative],
+            _ => &self.settings.further_parts_of(index)[part - 2].words[alternative],
+        }
+    }
+
+    /// True if the part of the pair needs a comma before it: the first and the second words always
+    /// do (the first word only in reversed pairs), the further parts according to their PairPart.
+    fn needs_comma(&self, role: AnchorRole) -> bool {
+        role.part < 2 || self.settings.further_parts_of(role.index)[role.part - 2].comma
+    }
+
+    /// Forgets the commas right before the completed anchors which need a comma, so they aren't
+    /// reported as misplaced commas of their pairs.
+    fn keep_misplaced_commas(&mut self, roles: &[(AnchorRole, &MatchedWord)]) {
+        for (role, word) in roles.iter() {
+            if !self.needs_comma(*role) {
+                continue;
+            }
+            for active in self.status.active.iter_mut().filter(|a| a.index == role.index) {
+                active.commas.retain(|a| a.line != word.insertion_point.line || a.span.end != word.insertion_point.span.start);
+            }
         }
     }
 
-    fn move_cursor_forward(&mut self, current_token: &PlainTextToken, tokens: &Lexer<PlainTextToken>) {
-        self.status.col += tokens.slice().chars().count() + 1;
-        if *current_token == PlainTextToken::NewLine {
-            self.status.col = 1;
-            self.status.row += 1;
+    /// Moves the active pairs one token further, the pairs which got too far are dropped.
+    fn advance(&mut self) {
+        let settings = &self.settings;
+        self.status.active.iter_mut().for_each(|a| a.distance += 1);
+        self.status.active.retain(|a| settings.options_of(a.index).max_distance.map_or(true, |max| a.distance <= max));
+    }
+
+    /// Continues the innermost active instance of every pair whose next part is one of the
+    /// completed anchors, and reports the missing (or misplaced) commas. Returns the indices of the
+    /// pairs which were continued or closed.
+    fn continue_pairs(&mut self, roles: &[(AnchorRole, &MatchedWord)], errors: &mut Vec<(usize, usize, Mistake)>) -> Vec<usize> {
+        let mut handled: Vec<usize> = Vec::new();
+
+        for active_index in (0..self.status.active.len()).rev() {
+            let active = &self.status.active[active_index];
+            let expected_part = if active.reversed.is_some() { 0 } else { active.next_part };
+            let found = roles.iter().find(|(role, _)| role.index == active.index && role.part == expected_part);
+            let (role, word) = match found {
+                Some(found) if !handled.contains(&active.index) => *found,
+                _ => continue,
+            };
+            handled.push(active.index);
+
+            if self.needs_comma(role) && !word.comma_not_needed {
+                self.push_mistakes_for_part(&self.status.active[active_index], role, word, errors);
+            }
+
+            let part_count = self.part_count(role.index);
+            let active = &mut self.status.active[active_index];
+            if active.reversed.is_none() && active.next_part + 1 < part_count {
+                active.next_part += 1;
+                active.distance = 0;
+                active.commas.clear();
+            } else {
+                self.status.active.remove(active_index);
+            }
         }
+
+        handled
     }
 
-    fn get_mistake_for_word(&self, pos1: usize, pos2: usize) -> (usize, usize, Mistake) {
-        (
-            self.status.row,
-            self.status.col,
-            Mistake::new_dyn(
-                format!("a(z) \"{}\" és \"{}\" szavak közé általában vesszőt teszünk (általában a második elé).", self.settings.first_words[pos1], self.settings.second_words[pos1][pos2]),
-                self.settings.probs[pos1]
-            )
-        )
+    /// Starts the pairs whose opening word is one of the completed anchors, except the ones that
+    /// were just continued or closed by the same word (so the closing word of a reversible pair
+    /// doesn't open the reversed pair). Without nesting, an already active instance of the pair is
+    /// restarted instead.
+    fn open_pairs(&mut self, roles: &[(AnchorRole, &MatchedWord)], handled: &[usize]) {
+        for (role, _) in roles.iter() {
+            let index = role.index;
+            if handled.contains(&index) {
+                continue;
+            }
+            let options = self.settings.options_of(index);
+            let reversed = match role.part {
+                0 => None,
+                1 if options.reversible => Some(role.alternative),
+                _ => continue,
+            };
+
+            let opened = ActivePair { index, reversed, next_part: 1, distance: 0, commas: Vec::new() };
+            let existing = self.status.active.iter().position(|a| a.index == index && a.reversed.is_some() == reversed.is_some());
+            match existing {
+                Some(existing) if !options.nested => self.status.active[existing] = opened,
+                _ => self.status.active.push(opened),
+            }
+        }
     }
 
-    fn set_active_word(&mut self, word: &str) {
-        let index = self.settings.first_words.iter().position(|a| a == word);
-        if let Some(pos) = index {
-            self.status.first_word_active[pos].set(true);
+    /// Reports the missing comma before the part. If there is a single comma somewhere else between
+    /// the parts, it is reported too, as a comma to be removed (with the rule id of the pair followed
+    /// by `,`).
+    fn push_mistakes_for_part(&self, active: &ActivePair, role: AnchorRole, word: &MatchedWord, errors: &mut Vec<(usize, usize, Mistake)>) {
+        let index = active.index;
+        let (opening, closing) = match active.reversed {
+            Some(second) => (self.anchor_text(index, 1, second), self.anchor_text(index, 0, 0)),
+            None => (self.anchor_text(index, 0, 0), self.anchor_text(index, role.part, role.alternative)),
+        };
+
+        let msg = if !active.commas.is_empty() {
+            format!("a(z) \"{}\" és \"{}\" szavak közötti vessző általában közvetlenül a(z) \"{}\" elé kerül.", opening, closing, closing)
+        } else {
+            format!("a(z) \"{}\" és \"{}\" szavak közé általában vesszőt teszünk (általában a második elé).", opening, closing)
+        };
+
+        if let [comma] = active.commas.as_slice() {
+            errors.push((
+                comma.line,
+                comma.col,
+                Mistake::new_dyn(
+                    format!("a(z) \"{}\" és \"{}\" szavak közötti vessző általában nem ide, hanem a(z) \"{}\" elé kerül.", opening, closing, closing),
+                    self.settings.probs[index]
+                )
+                    .with_position(comma.clone())
+                    .with_rule(DetectorKind::Pair, &[opening, closing, ","])
+                    .with_action(CommaAction::Remove)
+            ));
         }
+        errors.push((
+            word.position.line,
+            word.position.col,
+            Mistake::new_dyn(msg, self.settings.probs[index])
+                .with_position(word.position.clone())
+                .with_rule(DetectorKind::Pair, &[opening, closing])
+                .with_insertion_point(word.insertion_point.clone())
+        ));
     }
 }
+
 #[cfg(test)]
 mod tests {
-    use logos::Logos;
-
     use crate::detector::PairDetector;
-    use crate::model::{PlainTextToken, PairSettings};
+    use crate::model::{CommaAction, DetectorKind, Mistake, PairOptions, PairPart, PairSettings, ValidationError};
     use crate::traits::Detector;
 
     #[test]
     fn empty_str() {
-        let mut sut = PairDetector::new(PairSettings { first_words: Vec::new(), second_words: Vec::new(), probs: Vec::new()});
-        let mut tokens = PlainTextToken::lexer("");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = PairDetector::new(PairSettings { first_words: Vec::new(), second_words: Vec::new(), probs: Vec::new(), options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn comma_provided() {
-        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Mind a tanárok, mind a diákok egyetértenek abban, hogy változásra van szükség!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0, 1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Mind a tanárok, mind a diákok egyetértenek abban, hogy változásra van szükség!";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn semicolon_provided() {
-        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Mind a tanárok, mind a diákok egyetértenek abban; hogy változásra van szükség!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0, 1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Mind a tanárok, mind a diákok egyetértenek abban; hogy változásra van szükség!";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn one_comma_missing() {
-        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Mind a tanárok mind a diákok egyetértenek abban, hogy változásra van szükség!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0, 1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Mind a tanárok mind a diákok egyetértenek abban, hogy változásra van szükség!";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 1);
     }
 
     #[test]
     fn both_commas_missing() {
-        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0, 0.8]});
-        let mut tokens = PlainTextToken::lexer("Mind a tanárok mind a diákok egyetértenek abban hogy változásra van szükség!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0, 0.8], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Mind a tanárok mind a diákok egyetértenek abban hogy változásra van szükség!";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 2);
     }
 
     #[test]
     fn example_correct() {
-        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Ha mész vásárolni, akkor ne felejts el tejet hozni!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Ha mész vásárolni, akkor ne felejts el tejet hozni!";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn example_incorrect() {
-        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Ha mész vásárolni akkor ne felejts el tejet hozni!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Ha mész vásárolni akkor ne felejts el tejet hozni!";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn uppercase_and_quoted_words() {
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("HA mész vásárolni AKKOR hozz tejet!");
+        let quoted = sut.detect_errors("Ha mész azt mondja „akkor hozz tejet”!");
 
         assert_eq!(errors.len(), 1);
+        assert_eq!(quoted.len(), 0);
     }
 
     #[test]
     fn no_detection_over_sentences() {
-        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Mind hősök voltak ők. Mind az a tizenhárom, kit várt a vérpad!");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0, 1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Mind hősök voltak ők. Mind az a tizenhárom, kit várt a vérpad!";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn double_acute_words_incorrect() {
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("mielőtt")], second_words: vec![vec![String::from("előbb")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Mielőtt elindulsz előbb egyél!";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn structured_mistake() {
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let text = "Ha mész vásárolni akkor ne felejts el tejet hozni!";
+        let errors = sut.detect_errors(text);
+        let mistake = &errors[0].2;
+
+        assert_eq!(mistake.kind, DetectorKind::Pair);
+        assert_eq!(mistake.rule_id, "pair:ha:akkor");
+        assert_eq!(mistake.insert_at.as_ref().map(|a| a.span.clone()), Some(20..20));
+    }
+
+    #[test]
+    fn mismatched_settings_rejected() {
+        let sut = PairDetector::new(PairSettings { first_words: vec![String::from("mind"), String::from("abban")], second_words: vec![vec![String::from("mind")], vec![String::from("hogy")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() });
+
+        assert_eq!(sut.err(), Some(ValidationError::LengthMismatch { field: "probs", expected: 2, found: 1 }));
+    }
+
+    #[test]
+    fn empty_second_words_rejected() {
+        let sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![Vec::new()], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() });
+
+        assert_eq!(sut.err(), Some(ValidationError::EmptyWord { field: "second_words", index: 0 }));
+    }
+
+    fn pair_with_options(first: &str, second: &str, options: PairOptions) -> PairDetector {
+        PairDetector::new(PairSettings { first_words: vec![String::from(first)], second_words: vec![vec![String::from(second)]], probs: vec![1.0], options: vec![options], further_parts: Vec::new() }).unwrap()
+    }
+
+    #[test]
+    fn misplaced_comma() {
+        let mut sut = pair_with_options("ha", "akkor", PairOptions::default());
+        let errors = sut.detect_errors("Ha mész, vásárolni akkor ne felejts el tejet hozni!");
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].2.rule_id, "pair:ha:akkor:,");
+        assert_eq!(errors[0].2.action, CommaAction::Remove);
+        assert_eq!(errors[0].2.position.span, 8..9);
+        assert_eq!(errors[1].2.rule_id, "pair:ha:akkor");
+        assert_eq!(errors[1].2.action, CommaAction::Insert);
+        assert_eq!(errors[1].2.get_str(), "a(z) \"ha\" és \"akkor\" szavak közötti vessző általában közvetlenül a(z) \"akkor\" elé kerül.");
+    }
+
+    #[test]
+    fn max_distance() {
+        let mut sut = pair_with_options("ha", "akkor", PairOptions { max_distance: Some(3), ..PairOptions::default() });
+        let near = sut.detect_errors("Ha mész akkor hozz tejet!");
+        let far = sut.detect_errors("Ha mész a boltba akkor hozz tejet!");
+
+        assert_eq!(near.len(), 1);
+        assert_eq!(far.len(), 0);
+    }
+
+    #[test]
+    fn reversible() {
+        let mut sut = pair_with_options("ha", "akkor", PairOptions { reversible: true, ..PairOptions::default() });
+        let errors = sut.detect_errors("Akkor jövök ha hívsz. Akkor jövök, ha hívsz.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.rule_id, "pair:akkor:ha");
+        assert_eq!(errors[0].2.position.span, 14..16);
+    }
+
+    #[test]
+    fn closing_word_does_not_reverse() {
+        let mut sut = pair_with_options("ha", "akkor", PairOptions { reversible: true, ..PairOptions::default() });
+        let errors = sut.detect_errors("Ha esik, akkor jövök ha hívsz.");
 
         assert_eq!(errors.len(), 0);
     }
+
+    #[test]
+    fn not_reversible_by_default() {
+        let mut sut = pair_with_options("ha", "akkor", PairOptions::default());
+        let errors = sut.detect_errors("Akkor jövök ha hívsz.");
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn nested() {
+        let text = "Ha azt mondod, hogy ha esik akkor maradjak akkor maradok.";
+        let mut nested = pair_with_options("ha", "akkor", PairOptions { nested: true, ..PairOptions::default() });
+        let mut restarted = pair_with_options("ha", "akkor", PairOptions::default());
+
+        let inserted = |errors: Vec<(usize, usize, Mistake)>| errors.iter().filter(|(_, _, a)| a.action == CommaAction::Insert).count();
+
+        assert_eq!(inserted(nested.detect_errors(text)), 2);
+        assert_eq!(inserted(restarted.detect_errors(text)), 1);
+    }
+
+    #[test]
+    fn comma_before_nested_pair() {
+        let mut sut = pair_with_options("ha", "akkor", PairOptions { nested: true, ..PairOptions::default() });
+        let errors = sut.detect_errors("Ha azt mondod, ha esik akkor maradok akkor jövök.");
+
+        assert_eq!(errors.len(), 2);
+        for (_, _, mistake) in errors.iter() {
+            assert_eq!(mistake.get_str(), "a(z) \"ha\" és \"akkor\" szavak közé általában vesszőt teszünk (általában a második elé).");
+        }
+    }
+
+    #[test]
+    fn multi_word_anchors() {
+        let settings = PairSettings::new_from_string(String::from("nem csak;0.9;hanem|~is\negyrészt;0.95;más_részről másrészt")).unwrap();
+        let mut sut = PairDetector::new(settings).unwrap();
+        let errors = sut.detect_errors("Nem csak a tanárok hanem a diákok is. Egyrészt jó más részről drága.");
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].2.rule_id, "pair:nem csak:hanem");
+        assert_eq!(errors[1].2.rule_id, "pair:egyrészt:más részről");
+        assert_eq!(errors[1].2.position.span, 54..58);
+        assert_eq!(errors[1].2.insert_at.as_ref().map(|a| a.span.clone()), Some(53..53));
+    }
+
+    #[test]
+    fn every_part_needs_a_comma() {
+        let settings = PairSettings { first_words: vec![String::from("se")], second_words: vec![vec![String::from("se")]], probs: vec![0.8], options: Vec::new(), further_parts: vec![vec![PairPart::new(vec![String::from("se")], true)]] };
+        let mut sut = PairDetector::new(settings).unwrap();
+        let errors = sut.detect_errors("Nincs se kenyér se tej se vaj.");
+        let correct = sut.detect_errors("Nincs se kenyér, se tej, se vaj.");
+
+        assert_eq!(errors.iter().map(|(_, col, _)| *col).collect::<Vec<usize>>(), vec![17, 24]);
+        assert_eq!(correct.len(), 0);
+    }
+
+    #[test]
+    fn part_without_comma() {
+        let settings = PairSettings::new_from_string(String::from("nemcsak;0.95;hanem|~is")).unwrap();
+        let mut sut = PairDetector::new(settings).unwrap();
+        let errors = sut.detect_errors("Nemcsak a tanárok hanem a diákok is tudják, hanem is.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.rule_id, "pair:nemcsak:hanem");
+    }
+
+    #[test]
+    fn abbreviation_inside_pair() {
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("Ha pl. esik akkor otthon maradok. Ha esik. Akkor otthon maradok.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].1, 13);
+    }
+
+    #[test]
+    fn ordinals_inside_pair() {
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("A 2. pontban ha 2026. október 18. után II. Rákóczi és J. K. Rowling jön akkor maradok.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.rule_id, "pair:ha:akkor");
+    }
+
+    #[test]
+    fn ellipsis_inside_pair() {
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let errors = sut.detect_errors("Ha esik... akkor otthon maradok. Ha esik – akkor otthon maradok.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].1, 12);
+    }
+
+    #[test]
+    fn paragraph_ends_pair() {
+        let mut sut = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![1.0], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+
+        assert_eq!(sut.detect_errors("Ha esik\n\nakkor otthon maradok.").len(), 0);
+        assert_eq!(sut.detect_errors("Ha esik\nakkor otthon maradok.").len(), 1);
+    }
 }
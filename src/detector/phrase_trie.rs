@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+
+use crate::model::{Normalization, Phrase, Position};
+
+struct Node {
+    children: HashMap<String, usize>,
+    /// The indices of the phrases which end in this node.
+    entries: Vec<usize>,
+}
+
+impl Node {
+    fn new() -> Node {
+        Node {
+            children: HashMap::new(),
+            entries: Vec::new(),
+        }
+    }
+}
+
+/// A trie over the (normalized) words of the phrases of a dictionary.
+///
+/// The text is matched word by word, every partial match is a node of the trie, so the cost of a
+/// word doesn't depend on the number of phrases, only on the number of partial matches (which is
+/// at most the length of the longest phrase).
+pub(crate) struct PhraseTrie {
+    nodes: Vec<Node>,
+    phrases: Vec<Phrase>,
+}
+
+impl PhraseTrie {
+    /// The words have to be valid phrases (see Phrase::parse).
+    pub fn new(words: &[String], normalization: &Normalization) -> PhraseTrie {
+        let mut trie = PhraseTrie {
+            nodes: vec![Node::new()],
+            phrases: Vec::new(),
+        };
+
+        for (index, word) in words.iter().enumerate() {
+            let mut phrase = Phrase::parse(word).expect("the phrases are validated");
+            phrase.words = normalization.normalize_all(&phrase.words);
+
+            let mut node = 0;
+            for word in phrase.words.iter() {
+                node = match trie.nodes[node].children.get(word) {
+                    Some(child) => *child,
+                    None => {
+                        trie.nodes.push(Node::new());
+                        let child = trie.nodes.len() - 1;
+                        trie.nodes[node].children.insert(word.clone(), child);
+                        child
+                    }
+                };
+            }
+            trie.nodes[node].entries.push(index);
+            trie.phrases.push(phrase);
+        }
+
+        trie
+    }
+
+    pub fn phrase(&self, index: usize) -> &Phrase {
+        &self.phrases[index]
+    }
+}
+
+/// A word of the text that is part of a (partial) match.
+#[derive(Clone, Debug)]
+pub(crate) struct MatchedWord {
+    pub position: Position,
+    pub insertion_point: Position,
+    /// The word is preceded by a comma, or it starts a sentence or a quote.
+    pub comma_not_needed: bool,
+    /// The previous word was the end of a match.
+    pub after_match: bool,
+    /// The (normalized) previous token, if it is a word.
+    pub previous_word: Option<String>,
+}
+
+/// The partial matches of a PhraseTrie in the text processed so far.
+#[derive(Default)]
+pub(crate) struct PhraseMatches {
+    active: Vec<(usize, Vec<MatchedWord>)>,
+}
+
+impl PhraseMatches {
+    /// Continues the partial matches with the next (normalized) word of the text and starts a new
+    /// one. Returns the phrases which are completed by the word, together with the matched words.
+    pub fn next(&mut self, trie: &PhraseTrie, word: &str, matched: MatchedWord) -> Vec<(usize, Vec<MatchedWord>)> {
+        let mut completed = Vec::new();
+        let mut active = Vec::new();
+
+        for (node, mut words) in self.active.drain(..).chain(std::iter::once((0, Vec::new()))) {
+            if let Some(child) = trie.nodes[node].children.get(word) {
+                words.push(matched.clone());
+                for entry in trie.nodes[*child].entries.iter() {
+                    completed.push((*entry, words.clone()));
+                }
+                if !trie.nodes[*child].children.is_empty() {
+                    active.push((*child, words));
+                }
+            }
+        }
+
+        self.active = active;
+        completed
+    }
+
+    /// Drops the partial matches, a phrase can't continue over this point.
+    pub fn clear(&mut self) {
+        self.active.clear();
+    }
+}
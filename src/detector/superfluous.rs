@@ -0,0 +1,301 @@
+use crate::model::{CommaAction, DetectorKind, Mistake, Normalization, TokenKind, Position, SuperfluousSettings, Token, ValidationError};
+use crate::traits::Detector;
+use crate::detector::cursor::Cursor;
+use crate::detector::verb::is_likely_finite_verb;
+
+/// Contains the status of a SuperfluousDetector (cursor, last_comma, etc.)
+///
+/// Generally you shouldn't bother with it.
+struct SuperfluousStatus {
+    cursor: Cursor,
+    last_comma: Option<Position>,
+    /// True if there is whitespace (or nothing) before the last comma.
+    last_comma_detached: bool,
+    /// The second comma of a doubled comma, if it may be an opening quotation mark.
+    double_comma: Option<Position>,
+    /// A word which only makes the comma superfluous if its clause has no finite verb, and the
+    /// comma before it.
+    pending: Option<(usize, Position)>,
+}
+
+impl SuperfluousStatus {
+    pub fn new() -> SuperfluousStatus {
+        SuperfluousStatus {
+            cursor: Cursor::default(),
+            last_comma: None,
+            last_comma_detached: false,
+            double_comma: None,
+            pending: None,
+        }
+    }
+}
+
+/// Detects commas which should be removed. These are the commas before the given words (e.g. "stb")
+/// and the second comma of a doubled comma (",,").
+///
+/// Every word is given a probability, which means the following: what is the probability of the
+/// comma before that word being superfluous. The conjunctions marked with `joins_parts` (e.g. "és")
+/// only make the comma superfluous if they join parts of a sentence: their mistake is only
+/// reported if there is no probable finite verb after them in the clause ("Vettünk almát, és
+/// körtét."), otherwise they join two clauses ("Elment, és nem jött vissza."). It is only a guess,
+/// the words are not tagged, so it is not checked whether they join nouns.
+///
+/// Doubled commas are reported with a probability of 1, except when they are detached from the
+/// previous word and attached to the next one (",,Szia"): that is an opening quotation mark typed
+/// as two commas.
+///
+/// A comma between the subject and the predicate is not detected, that would need a parser.
+///
+/// The mistakes of this detector point at the comma that should be removed and their action is
+/// CommaAction::Remove.
+pub struct SuperfluousDetector {
+    settings: SuperfluousSettings,
+    normalization: Normalization,
+    words: Vec<String>,
+    status: SuperfluousStatus,
+}
+
+impl SuperfluousDetector {
+    /// Fails if the settings are not valid (see SuperfluousSettings::validate)
+    pub fn new(settings: SuperfluousSettings) -> Result<SuperfluousDetector, ValidationError> {
+        settings.validate()?;
+
+        let normalization = Normalization::default();
+        Ok(SuperfluousDetector {
+            words: normalization.normalize_all(&settings.words),
+            normalization,
+            settings,
+            status: SuperfluousStatus::new(),
+        })
+    }
+
+    /// Sets how the words are normalized before matching (lowercase by default).
+    pub fn with_normalization(mut self, normalization: Normalization) -> SuperfluousDetector {
+        self.words = normalization.normalize_all(&self.settings.words);
+        self.normalization = normalization;
+        self
+    }
+
+    fn get_mistake_for_word(&self, pos: usize, comma: Position) -> (usize, usize, Mistake) {
+        (
+            comma.line,
+            comma.col,
+            Mistake::new_dyn(
+                format!("a(z) \"{}\" szó elé általában nem teszünk vesszőt.", self.settings.words[pos]),
+                self.settings.probs[pos]
+            )
+                .with_position(comma)
+                .with_rule(DetectorKind::Superfluous, &[&self.settings.words[pos]])
+                .with_action(CommaAction::Remove)
+        )
+    }
+
+    /// Decides about the pending word: its comma is superfluous if the clause ends without a
+    /// probable finite verb.
+    fn check_clause(&mut self, token: &Token, errors: &mut Vec<(usize, usize, Mistake)>) {
+        if self.status.pending.is_none() {
+            return;
+        }
+
+        if token.kind.is_word() && is_likely_finite_verb(&token.text.to_lowercase()) {
+            self.status.pending = None;
+        } else if token.kind.replaces_comma() || token.kind.ends_sentence() {
+            let (pos, comma) = self.status.pending.take().expect("there is a pending word");
+            errors.push(self.get_mistake_for_word(pos, comma));
+        }
+    }
+
+    fn get_mistake_for_double_comma(&self, comma: Position) -> (usize, usize, Mistake) {
+        (
+            comma.line,
+            comma.col,
+            Mistake::new("két vessző követi egymást.", 1.0)
+                .with_position(comma)
+                .with_rule(DetectorKind::Superfluous, &[",,"])
+                .with_action(CommaAction::Remove)
+        )
+    }
+}
+
+impl Detector for SuperfluousDetector {
+    fn reset(&mut self) {
+        self.status = SuperfluousStatus::new();
+    }
+
+    fn next_token(&mut self, token: &Token, errors: &mut Vec<(usize, usize, Mistake)>) {
+        let position = self.status.cursor.next(token);
+        if token.kind == TokenKind::NewLine {
+            return;
+        }
+
+        if let Some(second) = self.status.double_comma.take() {
+            if position.span.start == second.span.end {
+                // ",,Szia": the commas are a quotation mark
+                self.status.last_comma = None;
+            } else {
+                errors.push(self.get_mistake_for_double_comma(second));
+            }
+        }
+        self.check_clause(token, errors);
+
+        let is_comma = token.kind == TokenKind::Comma && token.text == ",";
+        if let Some(comma) = self.status.last_comma.take() {
+            if is_comma {
+                if self.status.last_comma_detached && position.span.start == comma.span.end {
+                    self.status.double_comma = Some(position);
+                } else {
+                    errors.push(self.get_mistake_for_double_comma(position));
+                }
+                // The first comma is kept, so that it is checked against the next word as well
+                self.status.last_comma = Some(comma);
+                return;
+            }
+
+            let word = self.normalization.normalize(token.text);
+            if let Some(pos) = self.words.iter().position(|a| *a == word) {
+                if self.settings.joins_parts_of(pos) {
+                    self.status.pending = Some((pos, comma));
+                } else {
+                    errors.push(self.get_mistake_for_word(pos, comma));
+                }
+            }
+        }
+
+        if is_comma {
+            self.status.last_comma_detached = self.status.cursor.is_sentence_start()
+                || self.status.cursor.insertion_point().span.end < position.span.start;
+            self.status.last_comma = Some(position);
+        }
+    }
+
+    fn end_of_row(&mut self, _errors: &mut Vec<(usize, usize, Mistake)>) {
+        self.status.cursor.end_of_row();
+    }
+
+    fn end_of_text(&mut self, errors: &mut Vec<(usize, usize, Mistake)>) {
+        if let Some(second) = self.status.double_comma.take() {
+            errors.push(self.get_mistake_for_double_comma(second));
+        }
+        if let Some((pos, comma)) = self.status.pending.take() {
+            errors.push(self.get_mistake_for_word(pos, comma));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::detector::SuperfluousDetector;
+    use crate::model::{CommaAction, SuperfluousSettings};
+    use crate::traits::Detector;
+
+    fn settings() -> SuperfluousSettings {
+        SuperfluousSettings { words: vec![String::from("stb"), String::from("és")], probs: vec![1.0, 0.6], joins_parts: vec![false, true] }
+    }
+
+    #[test]
+    fn empty_str() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+        let text = "";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn no_superfluous_comma() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+        let text = "Vettünk almát, körtét stb. Azt mondta, hogy alma és körte.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn comma_before_word() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+        let text = "Vettünk almát, körtét, stb.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].0, errors[0].1), (1, 22));
+        assert_eq!(errors[0].2.action, CommaAction::Remove);
+        assert_eq!(errors[0].2.rule_id, "superfluous:stb");
+        assert_eq!(errors[0].2.position.span, 25..26);
+    }
+
+    #[test]
+    fn comma_before_word_next_line() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+        let text = "Alma,\nÉs körte.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.prob, 0.6);
+    }
+
+    #[test]
+    fn semicolon_ignored() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+        let text = "Ez az egyik; és ez a másik.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn double_comma() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+        let text = "Azt mondta,, hogy jön.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.position.span, 11..12);
+        assert_eq!(errors[0].2.rule_id, "superfluous:,,");
+    }
+
+    #[test]
+    fn conjunction_joining_clauses() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+
+        assert_eq!(sut.detect_errors("Elment, és nem jött vissza.").len(), 0);
+        assert_eq!(sut.detect_errors("Vettünk almát, és körtét.").len(), 1);
+        assert_eq!(sut.detect_errors("Vettünk almát, és körtét, amit megettünk.").len(), 1);
+        assert_eq!(sut.detect_errors("Vettünk almát, és körtét").len(), 1);
+    }
+
+    #[test]
+    fn clause_over_rows() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+        let mut errors = sut.detect_errors_in_row("Elment, és");
+        errors.append(&mut sut.detect_errors_in_row("nem jött vissza."));
+        sut.end_of_text(&mut errors);
+
+        assert_eq!(errors.len(), 0);
+
+        sut.reset();
+        let mut errors = sut.detect_errors_in_row("Vettünk almát, és");
+        errors.append(&mut sut.detect_errors_in_row("körtét"));
+        assert_eq!(errors.len(), 0);
+        sut.end_of_text(&mut errors);
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn double_comma_in_number() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+        let errors = sut.detect_errors("Az ára 1,,5 millió.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.position.span, 10..11);
+    }
+
+    #[test]
+    fn commas_as_quotation_mark() {
+        let mut sut = SuperfluousDetector::new(settings()).unwrap();
+
+        assert_eq!(sut.detect_errors("Azt mondta: ,,Szia\" és elment.").len(), 0);
+        assert_eq!(sut.detect_errors(",,Szia\", mondta.").len(), 0);
+        assert_eq!(sut.detect_errors("Alma ,, körte.").len(), 1);
+        assert_eq!(sut.detect_errors("Alma ,,").len(), 1);
+    }
+}
@@ -1,23 +1,38 @@
-use std::cell::Cell;
 use crate::traits::Detector;
-use logos::Lexer;
-use crate::model::{Mistake, PlainTextToken, TypicalSettings};
+use crate::detector::cursor::Cursor;
+use crate::detector::phrase_trie::{MatchedWord, PhraseMatches, PhraseTrie};
+use crate::detector::verb::{is_likely_finite_verb, NEGATIONS};
+use crate::model::{DetectorKind, Mistake, Normalization, Position, TokenKind, Token, TypicalSettings, ValidationError};
 
-/// Contains the status of a TypicalDetector (row, column, first_word_active)
+/// A word of the settings that appeared in the current sentence (since the last comma).
+#[derive(Clone)]
+struct ActiveWord {
+    /// The span of the word (or of the whole phrase) in the text.
+    position: Position,
+    /// The place right after the word.
+    after: Position,
+    /// The place before the first probable finite verb after the word.
+    before_verb: Option<Position>,
+}
+
+/// Contains the status of a TypicalDetector (cursor, matches, active words, previous word)
 ///
 /// Generally you shouldn't bother with it.
 struct TypicalStatus {
-    pub col: usize,
-    pub row: usize,
-    pub word_active: Vec<Cell<bool>>,
+    pub cursor: Cursor,
+    pub matches: PhraseMatches,
+    pub active: Vec<Option<ActiveWord>>,
+    /// The previous token (lowercase) if it is a word, and the place before it.
+    pub previous_word: Option<(String, Position)>,
 }
 
 impl TypicalStatus {
     fn new(words_len: usize) -> TypicalStatus {
         TypicalStatus {
-            col: 1,
-            row: 1,
-            word_active: vec![Cell::new(false); words_len],
+            cursor: Cursor::default(),
+            matches: PhraseMatches::default(),
+            active: vec![None; words_len],
+            previous_word: None,
         }
     }
 }
@@ -26,116 +41,285 @@ impl TypicalStatus {
 ///
 /// Example: Reméljük, nem esett baja. (there is an implicit "hogy")
 ///  We hope that he/she/it wasn't hurt.
+///
+/// The words can be phrases too (e.g. "mivel hogy"), their words have to follow each other without
+/// any punctuation between them.
+///
+/// The mistakes are reported at the end of the sentence, but they point at the word, and they
+/// contain the candidate places of the comma (see Mistake::candidates): right after the word, and
+/// before the first probable finite verb after it (or before its negation).
 pub struct TypicalDetector {
     settings: TypicalSettings,
+    normalization: Normalization,
+    trie: PhraseTrie,
     status: TypicalStatus,
 }
 
 impl TypicalDetector {
-    pub fn new(settings: TypicalSettings) -> TypicalDetector {
-        TypicalDetector {
+    /// Fails if the settings are not valid (see TypicalSettings::validate)
+    pub fn new(settings: TypicalSettings) -> Result<TypicalDetector, ValidationError> {
+        settings.validate()?;
+
+        let normalization = Normalization::default();
+        Ok(TypicalDetector {
             status: TypicalStatus::new(settings.words.len()),
+            trie: PhraseTrie::new(&settings.words, &normalization),
+            normalization,
             settings
-        }
+        })
     }
 
-    fn move_cursor_forward(&mut self, current_token: &PlainTextToken, tokens: &Lexer<PlainTextToken>) {
-        self.status.col += tokens.slice().chars().count() + 1;
-        if *current_token == PlainTextToken::NewLine {
-            self.status.col = 1;
-            self.status.row += 1;
-        }
+    /// Sets how the words are normalized before matching (lowercase by default).
+    pub fn with_normalization(mut self, normalization: Normalization) -> TypicalDetector {
+        self.trie = PhraseTrie::new(&self.settings.words, &normalization);
+        self.normalization = normalization;
+        self
     }
 
-    fn get_mistake_for_word(&self, pos1: usize) -> (usize, usize, Mistake) {
+    fn get_mistake_for_word(&self, pos1: usize, active: &ActiveWord) -> (usize, usize, Mistake) {
+        let mut candidates = vec![active.after.clone()];
+        if let Some(before_verb) = &active.before_verb {
+            if before_verb.span != active.after.span {
+                candidates.push(before_verb.clone());
+            }
+        }
+
         (
-            self.status.row,
-            self.status.col,
+            active.position.line,
+            active.position.col,
             Mistake::new_dyn(
                 format!("mondatokba, melyekben szerepel a(z) \"{}\" szó, gyakran teszünk vesszőt.", self.settings.words[pos1]),
                 self.settings.probs[pos1]
             )
+                .with_position(active.position.clone())
+                .with_rule(DetectorKind::Typical, &[&self.settings.words[pos1]])
+                .with_candidates(candidates)
         )
     }
 
-    fn set_active_word(&mut self, word: &str) {
-        let index = self.settings.words.iter().position(|a| a == word);
-        if let Some(pos) = index {
-            self.status.word_active[pos].set(true);
+    fn set_active_words(&mut self, text: &str, position: Position) {
+        let word = MatchedWord {
+            insertion_point: self.status.cursor.insertion_point(),
+            position: position.clone(),
+            comma_not_needed: false,
+            after_match: false,
+            previous_word: None,
+        };
+
+        for (pos, words) in self.status.matches.next(&self.trie, text, word) {
+            let first = &words[0].position;
+            self.status.active[pos] = Some(ActiveWord {
+                position: Position { span: first.span.start..position.span.end, ..first.clone() },
+                after: self.status.cursor.end_of_token(),
+                before_verb: None,
+            });
+        }
+    }
+
+    /// Reports the words which are still active at the end of the sentence.
+    fn report_active_words(&self, errors: &mut Vec<(usize, usize, Mistake)>) {
+        for (pos, active) in self.status.active.iter().enumerate() {
+            if let Some(active) = active {
+                errors.push(self.get_mistake_for_word(pos, active));
+            }
+        }
+    }
+
+    /// Remembers the place before the word for the active words, if it is the first probable finite
+    /// verb after them.
+    fn look_for_verb(&mut self, word: &str) {
+        if !is_likely_finite_verb(word) {
+            return;
+        }
+
+        let before_word = self.status.cursor.insertion_point();
+        let candidate = match &self.status.previous_word {
+            Some((previous, before_previous)) if NEGATIONS.contains(&previous.as_str()) => before_previous.clone(),
+            _ => before_word,
+        };
+
+        for active in self.status.active.iter_mut().flatten() {
+            if active.before_verb.is_none() && candidate.span.start >= active.after.span.start {
+                active.before_verb = Some(candidate.clone());
+            }
         }
     }
 }
 
 impl Detector for TypicalDetector {
-    fn detect_errors(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)> {
+    fn reset(&mut self) {
         self.status = TypicalStatus::new(self.settings.words.len());
-
-        self.detect_errors_in_row(tokens)
     }
 
-    fn detect_errors_in_row(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)> {
-        let mut errors = Vec::new();
-        while let Some(token) = tokens.next() {
-            let lowercase = String::from(tokens.slice()).to_lowercase();
+    fn next_token(&mut self, token: &Token, errors: &mut Vec<(usize, usize, Mistake)>) {
+        let position = self.status.cursor.next(token);
 
-            if token == PlainTextToken::EndOfSentence {
-                for (pos, _) in self.status.word_active.iter().enumerate().filter(|(_, a)| a.get()) {
-                    errors.push(self.get_mistake_for_word(pos));
-                }
-            }
+        if token.kind.ends_sentence() {
+            self.report_active_words(errors);
+        }
 
-            self.move_cursor_forward(&token, tokens);
-            self.set_active_word(lowercase.as_str());
+        // Phrases can't contain punctuation, not even commas
+        match token.kind {
+            kind if kind.is_word() => {
+                let lowercase = token.text.to_lowercase();
+                self.look_for_verb(&lowercase);
 
-            if token == PlainTextToken::Comma || token == PlainTextToken::EndOfSentence {
-                self.status.word_active.iter().for_each(|a| a.set(false));
+                let word = self.normalization.normalize(token.text);
+                self.set_active_words(&word, position.clone());
+                self.status.previous_word = Some((lowercase, self.status.cursor.insertion_point()));
             }
+            TokenKind::NewLine => {}
+            _ => {
+                self.status.matches.clear();
+                self.status.previous_word = None;
+            }
+        }
+
+        if token.kind.replaces_comma() || token.kind.ends_sentence() {
+            self.status.active.iter_mut().for_each(|a| *a = None);
         }
-        errors
+    }
+
+    fn end_of_row(&mut self, _errors: &mut Vec<(usize, usize, Mistake)>) {
+        self.status.cursor.end_of_row();
+    }
+
+    /// The words of a last sentence without a period are reported here.
+    fn end_of_text(&mut self, errors: &mut Vec<(usize, usize, Mistake)>) {
+        self.report_active_words(errors);
+        self.status.active.iter_mut().for_each(|a| *a = None);
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use logos::Logos;
-
     use crate::detector::TypicalDetector;
-    use crate::model::{PlainTextToken, TypicalSettings};
+    use crate::model::{Position, TypicalSettings};
     use crate::traits::Detector;
 
     #[test]
     fn empty_str() {
-        let mut sut = TypicalDetector::new(TypicalSettings { words: Vec::new(), probs: Vec::new()});
-        let mut tokens = PlainTextToken::lexer("");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = TypicalDetector::new(TypicalSettings { words: Vec::new(), probs: Vec::new()}).unwrap();
+        let text = "";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn comma_provided() {
-        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem"),], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Remélem, jól van.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem"),], probs: vec![1.0]}).unwrap();
+        let text = "Remélem, jól van.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn semicolon_provided() {
-        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem"),], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Remélem; jól van.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem"),], probs: vec![1.0]}).unwrap();
+        let text = "Remélem; jól van.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 0);
     }
 
     #[test]
     fn comma_missing() {
-        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem"),], probs: vec![1.0]});
-        let mut tokens = PlainTextToken::lexer("Remélem jól van.");
-        let errors = sut.detect_errors(&mut tokens);
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem"),], probs: vec![1.0]}).unwrap();
+        let text = "Remélem jól van.";
+        let errors = sut.detect_errors(text);
 
         assert_eq!(errors.len(), 1);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn colon_or_dash_provided() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem"),], probs: vec![1.0]}).unwrap();
+        let text = "Remélem: jól van. Remélem – és nem csak én – jól van.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn abbreviation_doesnt_end_sentence() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem"),], probs: vec![1.0]}).unwrap();
+        let text = "Remélem dr. Kovács is jól van, és jön.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 0);
+    }
+
+    #[test]
+    fn comma_missing_double_acute_word() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("tudvalevő"),], probs: vec![1.0]}).unwrap();
+        let text = "Tudvalevő nem szereti a spenótot.";
+        let errors = sut.detect_errors(text);
+
+        assert_eq!(errors.len(), 1);
+    }
+
+    #[test]
+    fn phrase_comma_missing() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("mivel hogy")], probs: vec![1.0] }).unwrap();
+        let errors = sut.detect_errors("Nem jött el mivel hogy beteg volt. Mivel esett otthon maradt.");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.rule_id, "typical:mivel hogy");
+    }
+
+    #[test]
+    fn trigger_span_and_candidates() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("reméljük")], probs: vec![0.8] }).unwrap();
+        let errors = sut.detect_errors("Reméljük nem esett baja.");
+        let mistake = &errors[0].2;
+
+        assert_eq!((errors[0].0, errors[0].1), (1, 1));
+        assert_eq!(mistake.position, Position { span: 0..10, line: 1, col: 1, utf16_col: 1 });
+        assert_eq!(mistake.insert_at, Some(Position { span: 10..10, line: 1, col: 9, utf16_col: 9 }));
+        assert_eq!(mistake.candidates.len(), 1);
+    }
+
+    #[test]
+    fn candidate_before_finite_verb() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("tudvalevő")], probs: vec![0.8] }).unwrap();
+        let errors = sut.detect_errors("Az tudvalevő a szomszédok szerint nem szereti a spenótot.");
+        let candidates = errors[0].2.candidates.iter().map(|a| a.span.clone()).collect::<Vec<_>>();
+
+        assert_eq!(errors[0].2.position.span, 3..13);
+        assert_eq!(candidates, vec![13..13, 35..35]);
+    }
+
+    #[test]
+    fn heading_without_period() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("tudvalevő")], probs: vec![0.8] }).unwrap();
+        let errors = sut.detect_errors("Az tudvalevő dolgok\n\nA szomszéd nem szereti a spenótot.");
+        let candidates = errors[0].2.candidates.iter().map(|a| a.span.clone()).collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(candidates, vec![13..13]);
+    }
+
+    #[test]
+    fn no_final_period() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("tudvalevő")], probs: vec![0.8] }).unwrap();
+        let with_break = sut.detect_errors("Az tudvalevő dolgok\n\nValami más.");
+        let errors = sut.detect_errors("Az tudvalevő dolgok");
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].2.position, with_break[0].2.position);
+        assert_eq!(errors[0].2.candidates, with_break[0].2.candidates);
+    }
+
+    #[test]
+    fn sentence_over_rows() {
+        let mut sut = TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem")], probs: vec![0.8] }).unwrap();
+        let mut errors = sut.detect_errors_in_row("Remélem a szomszéd");
+        errors.append(&mut sut.detect_errors_in_row("is, jól van."));
+        sut.end_of_text(&mut errors);
+
+        assert_eq!(errors.len(), 0);
+        assert_eq!(sut.detect_errors("Remélem a szomszéd is, jól van.").len(), 0);
+    }
+}
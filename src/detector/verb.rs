@@ -0,0 +1,41 @@
+/// Common finite verbs that the suffix rules below don't recognize.
+const COMMON_VERBS: &[&str] = &[
+    "van", "vannak", "volt", "voltak", "lesz", "lesznek", "nincs", "nincsenek", "kell", "lehet",
+    "jön", "jönnek", "megy", "mennek", "ment", "tud", "tudja", "akar", "akarja", "fog", "fogja",
+    "szeret", "szereti", "ad", "adja", "kap", "kapja", "hív", "hívja", "mond", "mondja", "lát",
+    "látja", "vár", "várja", "marad", "áll", "ül", "fekszik", "kér", "kéri", "hoz", "viszi",
+];
+
+/// The endings of past tense, of the plural persons and of the definite conjugation.
+const FINITE_SUFFIXES: &[&str] = &[
+    "tt", "unk", "ünk", "tok", "tek", "tök", "tam", "tem", "tad", "ted", "tál", "tél", "ták",
+    "ték", "szik", "zik", "juk", "jük", "játok", "itek",
+];
+
+/// The negations that precede the verb, the clause starts before them.
+pub(crate) const NEGATIONS: &[&str] = &["nem", "ne", "sem", "se"];
+
+/// A rough guess whether the (lowercase) word is a finite verb, based on a list of common verbs
+/// and on typical conjugation suffixes. It is used to find the probable start of a clause.
+pub(crate) fn is_likely_finite_verb(word: &str) -> bool {
+    if COMMON_VERBS.contains(&word) {
+        return true;
+    }
+
+    word.chars().count() > 3 && FINITE_SUFFIXES.iter().any(|a| word.ends_with(a))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::detector::verb::is_likely_finite_verb;
+
+    #[test]
+    fn finite_verbs() {
+        for word in ["esett", "van", "mentünk", "eszik", "láttátok", "olvastam"].iter() {
+            assert!(is_likely_finite_verb(word), "{}", word);
+        }
+        for word in ["baja", "jól", "tejet", "ház", "hogy"].iter() {
+            assert!(!is_likely_finite_verb(word), "{}", word);
+        }
+    }
+}
@@ -0,0 +1,191 @@
+use crate::model::{CommaAction, Edit, Finding, FixResult, Mistake, Position};
+
+/// Corrects the commas of the text: inserts the missing ones and removes the superfluous ones.
+///
+/// Only mistakes with a probability of at least `threshold` are applied, missing commas are only
+/// inserted if the mistake has an insertion point, and only one candidate place (see
+/// Mistake::candidates). Mistakes suggesting the same change result in a
+/// single edit, and nothing is inserted where there already is a comma or semicolon. Everything
+/// else in the text is left untouched.
+///
+/// The mistakes have to come from processing the whole text at once (not row by row), since the
+/// positions are byte offsets in the text.
+pub fn fix<'a, I>(text: &str, mistakes: I, threshold: f64) -> FixResult
+where
+    I: IntoIterator<Item = &'a Mistake>,
+{
+    let candidates = mistakes.into_iter()
+        .filter_map(|a| target(a.action, a).map(|position| (position, a.action, a.prob, vec![a.rule_id.clone()])));
+
+    apply(text, candidates, threshold)
+}
+
+/// Corrects the commas of the text using the findings of an Analyzer and their combined
+/// probability (see `fix`).
+pub fn fix_findings(text: &str, findings: &[Finding], threshold: f64) -> FixResult {
+    let candidates = findings.iter()
+        .filter_map(|a| target(a.action, &a.sources[0].1).map(|position| {
+            (position, a.action, a.prob, a.sources.iter().map(|(_, mistake)| mistake.rule_id.clone()).collect())
+        }));
+
+    apply(text, candidates, threshold)
+}
+
+/// The part of the text that has to be replaced to correct the mistake.
+fn target(action: CommaAction, mistake: &Mistake) -> Option<&Position> {
+    match action {
+        CommaAction::Insert if mistake.candidates.len() <= 1 => mistake.insert_at.as_ref(),
+        CommaAction::Insert => None,
+        CommaAction::Remove => Some(&mistake.position),
+    }
+}
+
+fn apply<'a, I>(text: &str, candidates: I, threshold: f64) -> FixResult
+where
+    I: Iterator<Item = (&'a Position, CommaAction, f64, Vec<String>)>,
+{
+    let mut edits: Vec<Edit> = Vec::new();
+
+    for (position, action, prob, rule_ids) in candidates.filter(|(_, _, prob, _)| *prob >= threshold) {
+        let span = position.span.clone();
+        if span.end > text.len() || !text.is_char_boundary(span.start) || !text.is_char_boundary(span.end) {
+            continue;
+        }
+
+        let replacement = match action {
+            CommaAction::Insert if text[span.start..].starts_with([',', ';']) => continue,
+            CommaAction::Insert => ",",
+            CommaAction::Remove if &text[span.clone()] != "," => continue,
+            CommaAction::Remove => "",
+        };
+
+        match edits.iter_mut().find(|a| a.position.span == span) {
+            Some(edit) => {
+                edit.prob = edit.prob.max(prob);
+                for rule_id in rule_ids {
+                    if !edit.rule_ids.contains(&rule_id) {
+                        edit.rule_ids.push(rule_id);
+                    }
+                }
+            }
+            None => edits.push(Edit { position: position.clone(), text: String::from(replacement), prob, rule_ids }),
+        }
+    }
+
+    edits.sort_by_key(|a| a.position.span.start);
+
+    let mut fixed = String::with_capacity(text.len() + edits.len());
+    let mut last = 0;
+    for edit in edits.iter() {
+        fixed.push_str(&text[last..edit.position.span.start]);
+        fixed.push_str(&edit.text);
+        last = edit.position.span.end;
+    }
+    fixed.push_str(&text[last..]);
+
+    FixResult {
+        text: fixed,
+        edits,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::analyzer::Analyzer;
+    use crate::detector::{naive_fixture, PairDetector, SuperfluousDetector, TypicalDetector};
+    use crate::fix::{fix, fix_findings};
+    use crate::model::{CombinationRule, Finding, Mistake, PairSettings, Position, SuperfluousSettings, TokenStream, TypicalSettings};
+    use crate::traits::Detector;
+
+    #[test]
+    fn nothing_to_fix() {
+        let text = "Azt mondta, hogy jön.";
+        let errors = naive_fixture().detect_errors_in_stream(&TokenStream::new(text));
+        let result = fix(text, errors.iter().map(|(_, _, a)| a), 0.5);
+
+        assert_eq!(result.text, text);
+        assert_eq!(result.edits.len(), 0);
+    }
+
+    #[test]
+    fn keeps_whitespace() {
+        let text = "Azt mondta\n\t hogy  jön.\nAzt hitte   hogy nem.";
+        let errors = naive_fixture().detect_errors_in_stream(&TokenStream::new(text));
+        let result = fix(text, errors.iter().map(|(_, _, a)| a), 0.5);
+
+        assert_eq!(result.text, "Azt mondta,\n\t hogy  jön.\nAzt hitte,   hogy nem.");
+        assert_eq!(result.edits.len(), 2);
+        assert_eq!((result.edits[0].position.line, result.edits[0].position.col), (1, 11));
+        assert_eq!(result.edits[0].rule_ids, vec![String::from("before:hogy")]);
+    }
+
+    #[test]
+    fn threshold() {
+        let text = "Ha esik akkor otthon maradok.";
+        let errors = naive_fixture().detect_errors_in_stream(&TokenStream::new(text));
+
+        assert_eq!(fix(text, errors.iter().map(|(_, _, a)| a), 0.5).text, text);
+        assert_eq!(fix(text, errors.iter().map(|(_, _, a)| a), 0.4).text, "Ha esik, akkor otthon maradok.");
+    }
+
+    #[test]
+    fn overlapping_suggestions() {
+        let text = "Ha esik akkor otthon maradok.";
+        let stream = TokenStream::new(text);
+        let mut errors = naive_fixture().detect_errors_in_stream(&stream);
+        errors.append(&mut PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![0.8], options: Vec::new(), further_parts: Vec::new() }).unwrap().detect_errors_in_stream(&stream));
+        let result = fix(text, errors.iter().map(|(_, _, a)| a), 0.3);
+
+        assert_eq!(result.text, "Ha esik, akkor otthon maradok.");
+        assert_eq!(result.edits.len(), 1);
+        assert_eq!(result.edits[0].prob, 0.8);
+        assert_eq!(result.edits[0].rule_ids, vec![String::from("before:akkor"), String::from("pair:ha:akkor")]);
+    }
+
+    #[test]
+    fn moves_misplaced_comma() {
+        let text = "Ha mész, vásárolni akkor ne felejts el tejet.";
+        let errors = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![0.8], options: Vec::new(), further_parts: Vec::new() }).unwrap().detect_errors(text);
+        let result = fix(text, errors.iter().map(|(_, _, a)| a), 0.5);
+
+        assert_eq!(result.text, "Ha mész vásárolni, akkor ne felejts el tejet.");
+        assert_eq!(result.edits.iter().map(|a| a.rule_ids[0].as_str()).collect::<Vec<_>>(), vec!["pair:ha:akkor:,", "pair:ha:akkor"]);
+    }
+
+    #[test]
+    fn findings_with_several_candidates_skipped() {
+        let text = "Remélem jól van. Ha esik akkor otthon maradok.";
+        let mut analyzer = Analyzer::new(CombinationRule::NoisyOr);
+        analyzer.add_detector("naive", Box::new(naive_fixture()));
+        analyzer.add_detector("typical", Box::new(TypicalDetector::new(TypicalSettings { words: vec![String::from("remélem")], probs: vec![1.0] }).unwrap()));
+        let result = fix_findings(text, &analyzer.analyze(text), 0.0);
+
+        assert_eq!(result.text, "Remélem jól van. Ha esik, akkor otthon maradok.");
+    }
+
+    #[test]
+    fn mistakes_without_insertion_point_skipped() {
+        let text = "Azt mondta hogy jön.";
+        let mut errors = naive_fixture().detect_errors_in_stream(&TokenStream::new(text));
+        let without_insertion_point = Mistake::new("vessző hiányzik", 1.0).with_position(Position { line: 1, col: 1, utf16_col: 1, span: 0..3 });
+        errors.push((1, 1, without_insertion_point));
+        let result = fix(text, errors.iter().map(|(_, _, a)| a), 0.5);
+
+        assert!(errors[1].2.insert_at.is_none());
+        assert_eq!(result.text, "Azt mondta, hogy jön.");
+        assert_eq!(result.edits.len(), 1);
+        assert_eq!(fix_findings(text, &[Finding::new(1, 1, "kézi", errors[1].2.clone())], 0.0).text, text);
+    }
+
+    #[test]
+    fn removes_superfluous_commas() {
+        let text = "Alma, körte,, stb.";
+        let mut analyzer = Analyzer::new(CombinationRule::NoisyOr);
+        analyzer.add_detector("superfluous", Box::new(SuperfluousDetector::new(SuperfluousSettings { words: vec![String::from("stb")], probs: vec![0.9], joins_parts: Vec::new() }).unwrap()));
+        let result = fix_findings(text, &analyzer.analyze(text), 0.5);
+
+        assert_eq!(result.text, "Alma, körte stb.");
+        assert_eq!(result.edits.len(), 2);
+        assert_eq!(result.edits[0].text, "");
+    }
+}
@@ -0,0 +1,3 @@
+mod autofix;
+
+pub use autofix::{fix, fix_findings};
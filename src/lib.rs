@@ -1,3 +1,9 @@
+pub mod analyzer;
 pub mod detector;
+pub mod fix;
+#[cfg(feature = "markdown")]
+pub mod markdown;
 pub mod model;
+pub mod segment;
+pub mod stream;
 pub mod traits;
\ No newline at end of file
@@ -0,0 +1,3 @@
+mod prose;
+
+pub use prose::{prose_spans, prose_tokens};
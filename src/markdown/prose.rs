@@ -0,0 +1,149 @@
+use std::ops::Range;
+
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+
+use crate::model::{lex, TokenKind, Tokens};
+use crate::segment::{default_segmenter, Segmented};
+
+/// The tokens of the prose of a Markdown document: the text of the paragraphs, list items, block
+/// quotes and table cells. Headings, code blocks, HTML and images are left out, an inline code
+/// span is a single Text token and only the text of a link is kept (not its URL).
+///
+/// The positions of the tokens are in the Markdown source, every block ends with a ParagraphBreak.
+/// The tokens are segmented like the ones of Tokens::new.
+pub fn prose_tokens(source: &str) -> Segmented<'static, '_, Tokens<'_>> {
+    default_segmenter().segment(Tokens::from_valid_spans(source, prose_spans(source)))
+}
+
+/// The kinds and byte spans of the tokens of the prose of a Markdown document (see prose_tokens),
+/// they are not segmented. They are in increasing order and don't overlap, so they can be given to
+/// Tokens::from_spans, e.g. for an other Segmenter.
+pub fn prose_spans(source: &str) -> Vec<(TokenKind, Range<usize>)> {
+    let mut spans = Vec::new();
+    // The number of open elements whose text is left out
+    let mut skipped = 0;
+
+    let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+    for (event, range) in Parser::new_ext(source, options).into_offset_iter() {
+        match event {
+            Event::Start(tag) => {
+                if is_skipped(&tag) {
+                    skipped += 1;
+                }
+                if !is_inline(&tag) {
+                    push_break(&mut spans);
+                }
+            }
+            Event::End(tag) => {
+                if is_skipped_end(&tag) {
+                    skipped -= 1;
+                }
+                if !is_inline_end(&tag) {
+                    push_break(&mut spans);
+                }
+            }
+            _ if skipped > 0 => {}
+            Event::Text(text) => {
+                // Escaped characters and entities are not the same in the source
+                if source[range.clone()] == *text {
+                    for (kind, span) in lex(&text) {
+                        push_span(&mut spans, kind, range.start + span.start..range.start + span.end);
+                    }
+                } else {
+                    push_span(&mut spans, TokenKind::Other, range);
+                }
+            }
+            Event::Code(_) => push_span(&mut spans, TokenKind::Text, range),
+            Event::SoftBreak | Event::HardBreak => {
+                if let Some(newline) = source[range.clone()].find('\n') {
+                    let start = range.start + newline;
+                    push_span(&mut spans, TokenKind::NewLine, start..start + 1);
+                }
+            }
+            _ => {}
+        }
+    }
+    if matches!(spans.last(), Some((TokenKind::ParagraphBreak, _))) {
+        spans.pop();
+    }
+
+    spans
+}
+
+/// Adds the span, unless it would overlap the previous one.
+fn push_span(spans: &mut Vec<(TokenKind, Range<usize>)>, kind: TokenKind, span: Range<usize>) {
+    if spans.last().map_or(true, |(_, last)| last.end <= span.start) {
+        spans.push((kind, span));
+    }
+}
+
+/// Ends the current block, if it isn't empty. The break is placed right after its last token.
+fn push_break(spans: &mut Vec<(TokenKind, Range<usize>)>) {
+    match spans.last() {
+        Some((TokenKind::ParagraphBreak, _)) | None => {}
+        Some((_, last)) => {
+            let end = last.end;
+            spans.push((TokenKind::ParagraphBreak, end..end));
+        }
+    }
+}
+
+fn is_skipped(tag: &Tag) -> bool {
+    matches!(tag, Tag::Heading { .. } | Tag::CodeBlock(_) | Tag::HtmlBlock | Tag::Image { .. } | Tag::MetadataBlock(_))
+}
+
+fn is_skipped_end(tag: &TagEnd) -> bool {
+    matches!(tag, TagEnd::Heading(_) | TagEnd::CodeBlock | TagEnd::HtmlBlock | TagEnd::Image | TagEnd::MetadataBlock(_))
+}
+
+fn is_inline(tag: &Tag) -> bool {
+    matches!(tag, Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Superscript | Tag::Subscript | Tag::Link { .. } | Tag::Image { .. })
+}
+
+fn is_inline_end(tag: &TagEnd) -> bool {
+    matches!(tag, TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Superscript | TagEnd::Subscript | TagEnd::Link | TagEnd::Image)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::detector::NaiveDetector;
+    use crate::markdown::prose_tokens;
+    use crate::model::{NaiveSettings, TokenKind};
+    use crate::traits::Detector;
+
+    const SOURCE: &str = "# Tudom hogy ez a cím\n\
+        \n\
+        Azt *mondta* hogy jön.\n\
+        \n\
+        ```\n\
+        Tudom hogy kód\n\
+        ```\n\
+        \n\
+        - Első elem\n\
+        - [Link hogy](https://pelda.hu/hogy) és `hogy`\n\
+        \n\
+        > Idézet,\n\
+        > amiben hogy van.\n\
+        \n\
+        | Fej | Cella |\n\
+        |-----|-------|\n\
+        | a   | tudom hogy |\n";
+
+    #[test]
+    fn only_prose() {
+        let words = prose_tokens(SOURCE).filter(|a| a.kind.is_word()).map(|a| a.text).collect::<Vec<_>>();
+
+        assert_eq!(words, vec!["Azt", "mondta", "hogy", "jön", "Első", "elem", "Link", "hogy", "és", "`hogy`", "Idézet", "amiben", "hogy", "van", "Fej", "Cella", "a", "tudom", "hogy"]);
+        assert_eq!(prose_tokens("Alma &amp; körte").map(|a| a.kind).collect::<Vec<_>>(), vec![TokenKind::Text, TokenKind::Other, TokenKind::Text]);
+    }
+
+    #[test]
+    fn positions_in_source() {
+        let mut sut = NaiveDetector::new(NaiveSettings { words: vec![String::from("hogy")], probs: vec![1.0], exceptions: Vec::new() }).unwrap();
+        let errors = sut.detect_errors_in_markdown(SOURCE);
+
+        let places = errors.iter().map(|(row, col, _)| (*row, *col)).collect::<Vec<_>>();
+        assert_eq!(places, vec![(3, 14), (10, 9), (13, 10), (17, 15)]);
+        assert_eq!(&SOURCE[errors[0].2.position.span.clone()], "hogy");
+    }
+}
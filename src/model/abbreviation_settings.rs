@@ -0,0 +1,109 @@
+use crate::model::{Phrase, SettingsError, ValidationError};
+use crate::model::settings_parser::{settings_rows, strict, SettingsRow};
+use crate::model::validation::check_length;
+
+/// Contains the abbreviations that are used by the Segmenter: a period after them doesn't end the
+/// sentence.
+///
+/// Loads words from files. Every row of the file has the following format: `abbreviation` or
+/// `abbreviation;end`, everything after a `#` is a comment. The abbreviation is written without its
+/// period (`pl`, `stb`), an abbreviation with more periods is listed by its parts (`i` and `sz` for
+/// "i. sz."). `end` marks the abbreviations which often end a sentence (e.g. `stb`), the period
+/// after them ends the sentence if the next word is capitalized.
+#[derive(Clone)]
+pub struct AbbreviationSettings {
+    pub words: Vec<String>,
+    /// The abbreviation can end a sentence.
+    pub sentence_end: Vec<bool>,
+}
+
+impl AbbreviationSettings {
+    /// The bundled list of Hungarian abbreviations (see `dictionaries/abbreviations.csv`).
+    pub fn hungarian_default() -> AbbreviationSettings {
+        AbbreviationSettings::new_from_string(String::from(include_str!("../../dictionaries/abbreviations.csv")))
+            .expect("the bundled dictionary is valid")
+    }
+
+    /// Loads the settings, fails on the first row that is not valid.
+    pub fn new_from_string(content: String) -> Result<AbbreviationSettings, SettingsError> {
+        strict(AbbreviationSettings::new_from_string_lenient(content))
+    }
+
+    /// Loads the settings, skipping every row that is not valid. The problems of these rows are
+    /// returned alongside the settings.
+    pub fn new_from_string_lenient(content: String) -> (AbbreviationSettings, Vec<SettingsError>) {
+        let mut settings = AbbreviationSettings {
+            words: Vec::new(),
+            sentence_end: Vec::new(),
+        };
+        let mut errors = Vec::new();
+
+        for row in settings_rows(&content) {
+            if let Err(error) = settings.add_row(&row) {
+                errors.push(error);
+            }
+        }
+
+        (settings, errors)
+    }
+
+    /// Checks that every abbreviation has a sentence end flag, and the abbreviations are unique,
+    /// single words.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_length("sentence_end", self.words.len(), self.sentence_end.len())?;
+
+        for (index, word) in self.words.iter().enumerate() {
+            if word.trim().is_empty() {
+                return Err(ValidationError::EmptyWord { field: "words", index });
+            }
+            if !Phrase::parse(word).is_some_and(|a| a.is_single_word()) || word.contains('.') {
+                return Err(ValidationError::InvalidPhrase { index, word: word.clone() });
+            }
+            if self.words[..index].contains(word) {
+                return Err(ValidationError::DuplicateEntry { index, word: word.clone() });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_row(&mut self, row: &SettingsRow) -> Result<(), SettingsError> {
+        row.expect_columns(1, 2)?;
+        let word = row.word(0)?;
+        let sentence_end = row.flag(1, "end")?;
+        row.check_duplicate(0, &self.words)?;
+
+        self.words.push(word);
+        self.sentence_end.push(sentence_end);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{AbbreviationSettings, SettingsError, SettingsErrorKind, ValidationError};
+
+    #[test]
+    fn load() {
+        let settings = AbbreviationSettings::new_from_string(String::from("pl  # például\nstb;end\n")).unwrap();
+
+        assert_eq!(settings.words, vec![String::from("pl"), String::from("stb")]);
+        assert_eq!(settings.sentence_end, vec![false, true]);
+    }
+
+    #[test]
+    fn invalid_option() {
+        let error = AbbreviationSettings::new_from_string(String::from("stb;vége")).err().unwrap();
+
+        assert_eq!(error, SettingsError::new(1, 5, "vége", SettingsErrorKind::InvalidOption));
+    }
+
+    #[test]
+    fn validate() {
+        let settings = AbbreviationSettings { words: vec![String::from("i. sz")], sentence_end: vec![false] };
+
+        assert_eq!(settings.validate(), Err(ValidationError::InvalidPhrase { index: 0, word: String::from("i. sz") }));
+        assert!(AbbreviationSettings::hungarian_default().validate().is_ok());
+    }
+}
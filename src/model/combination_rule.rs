@@ -0,0 +1,20 @@
+/// Determines how the probabilities of the detectors are combined, if more than one of them reports
+/// a mistake at the same place.
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub enum CombinationRule {
+    /// Treats the detectors as independent evidence: the result is `1 - (1 - p1) * (1 - p2) * ...`,
+    /// so every additional detector increases the probability.
+    #[default]
+    NoisyOr,
+    /// The result is the highest probability given by any of the detectors.
+    Max,
+}
+
+impl CombinationRule {
+    pub fn combine<I: IntoIterator<Item = f64>>(&self, probs: I) -> f64 {
+        match self {
+            CombinationRule::NoisyOr => 1.0 - probs.into_iter().fold(1.0, |acc, p| acc * (1.0 - p)),
+            CombinationRule::Max => probs.into_iter().fold(0.0, f64::max),
+        }
+    }
+}
@@ -0,0 +1,102 @@
+/// Which neighbour of the trigger word a ContextException looks at.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContextSide {
+    /// The word right before the trigger word (or phrase).
+    Preceding,
+    /// The word right after the trigger word (or phrase).
+    Following,
+}
+
+/// Overrides the probability of a dictionary entry if the trigger word is preceded (or followed)
+/// by the given word.
+///
+/// In the settings files it is written as `<word=probability` (preceded by) or `>word=probability`
+/// (followed by), the probability can be left out, in that case it is 0. Example: `ha` usually
+/// needs a comma before it, but not in "csak ha", "még ha" or "mint ha": `<csak=0 <még=0.1 <mint`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ContextException {
+    pub side: ContextSide,
+    pub word: String,
+    pub prob: f64,
+}
+
+impl ContextException {
+    pub fn new(side: ContextSide, word: &str, prob: f64) -> ContextException {
+        ContextException {
+            side,
+            word: String::from(word),
+            prob,
+        }
+    }
+
+    /// Returns None if the text is not in the `<word=probability` or `>word=probability` format.
+    /// The probability is not checked to be in the 0..=1 range.
+    pub fn parse(text: &str) -> Option<ContextException> {
+        let side = match text.chars().next()? {
+            '<' => ContextSide::Preceding,
+            '>' => ContextSide::Following,
+            _ => return None,
+        };
+
+        let mut parts = text[1..].splitn(2, '=');
+        let word = parts.next().unwrap_or("").trim();
+        let prob = match parts.next() {
+            Some(prob) => prob.trim().parse::<f64>().ok()?,
+            None => 0.0,
+        };
+
+        if word.is_empty() || word.contains(char::is_whitespace) {
+            return None;
+        }
+
+        Some(ContextException::new(side, word, prob))
+    }
+
+    /// Returns the probability of an entry in the given context: the probability of the matching
+    /// exception (it can be higher or lower than `prob`), or `prob` if none of them match. If more
+    /// exceptions match, the last one is used. The words have to be normalized the same way.
+    pub fn apply<F: Fn(&str) -> String>(exceptions: &[ContextException], side: ContextSide, word: Option<&str>, prob: f64, normalize: F) -> f64 {
+        let word = match word {
+            Some(word) => word,
+            None => return prob,
+        };
+
+        exceptions.iter()
+            .filter(|a| a.side == side && normalize(&a.word) == word)
+            .last()
+            .map_or(prob, |a| a.prob)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ContextException, ContextSide};
+
+    #[test]
+    fn parse() {
+        assert_eq!(ContextException::parse("<csak=0.1"), Some(ContextException::new(ContextSide::Preceding, "csak", 0.1)));
+        assert_eq!(ContextException::parse(">nem"), Some(ContextException::new(ContextSide::Following, "nem", 0.0)));
+        assert_eq!(ContextException::parse("csak=0.1"), None);
+        assert_eq!(ContextException::parse("<=0.1"), None);
+        assert_eq!(ContextException::parse("<csak=sok"), None);
+    }
+
+    #[test]
+    fn apply() {
+        let exceptions = vec![ContextException::new(ContextSide::Preceding, "csak", 0.0), ContextException::new(ContextSide::Preceding, "még", 0.2)];
+        let normalize = |a: &str| a.to_lowercase();
+
+        assert_eq!(ContextException::apply(&exceptions, ContextSide::Preceding, Some("még"), 0.5, normalize), 0.2);
+        assert_eq!(ContextException::apply(&exceptions, ContextSide::Following, Some("még"), 0.5, normalize), 0.5);
+        assert_eq!(ContextException::apply(&exceptions, ContextSide::Preceding, None, 0.5, normalize), 0.5);
+    }
+
+    #[test]
+    fn apply_overrides() {
+        let exceptions = vec![ContextException::new(ContextSide::Following, "hogy", 0.9), ContextException::new(ContextSide::Following, "Hogy", 0.7)];
+        let normalize = |a: &str| a.to_lowercase();
+
+        assert_eq!(ContextException::apply(&exceptions[..1], ContextSide::Following, Some("hogy"), 0.3, normalize), 0.9);
+        assert_eq!(ContextException::apply(&exceptions, ContextSide::Following, Some("hogy"), 0.3, normalize), 0.7);
+    }
+}
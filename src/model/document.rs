@@ -0,0 +1,124 @@
+use std::ops::Range;
+
+use crate::model::{Token, TokenKind, Tokens, TokenStream};
+
+/// A paragraph of a Document, the text between two paragraph breaks (see
+/// TokenKind::ParagraphBreak).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Paragraph<'a> {
+    /// The text of the paragraph, from the start of its first token to the end of its last one.
+    pub text: &'a str,
+    /// The byte span of the paragraph in the whole text.
+    pub span: Range<usize>,
+    /// The first and the last line of the paragraph (both inclusive).
+    pub lines: (usize, usize),
+    /// The indices of the tokens of the paragraph in the TokenStream of the Document (the line
+    /// breaks around it are left out).
+    pub tokens: Range<usize>,
+}
+
+/// A text split into tokens and paragraphs.
+///
+/// The detectors see where the paragraphs end from the ParagraphBreak tokens of the stream (see
+/// Document::tokens), the paragraphs can be used to group their mistakes or to process the
+/// paragraphs one by one.
+#[derive(Clone, Debug)]
+pub struct Document<'a> {
+    tokens: TokenStream<'a>,
+    paragraphs: Vec<Paragraph<'a>>,
+}
+
+impl<'a> Document<'a> {
+    /// Splits the text using the built-in tokenizer, the paragraphs are separated by empty lines.
+    pub fn new(text: &'a str) -> Document<'a> {
+        Document::from_tokens(text, Tokens::new(text))
+    }
+
+    /// Creates a document from the tokens of the text, e.g. from Tokens::with_segmenter for other
+    /// paragraph breaks. The paragraphs are separated by the ParagraphBreak tokens, the paragraphs
+    /// without tokens are left out.
+    pub fn from_tokens<I: IntoIterator<Item = Token<'a>>>(text: &'a str, tokens: I) -> Document<'a> {
+        let tokens = TokenStream::from_tokens(tokens);
+        let mut paragraphs = Vec::new();
+        let mut current: Option<Range<usize>> = None;
+
+        for (index, token) in tokens.iter().enumerate() {
+            if token.kind == TokenKind::ParagraphBreak {
+                if let Some(range) = current.take() {
+                    paragraphs.push(Document::paragraph(text, tokens.tokens(), range));
+                }
+            } else if token.kind != TokenKind::NewLine {
+                current.get_or_insert(index..index).end = index + 1;
+            }
+        }
+        if let Some(range) = current {
+            paragraphs.push(Document::paragraph(text, tokens.tokens(), range));
+        }
+
+        Document {
+            tokens,
+            paragraphs,
+        }
+    }
+
+    /// All the tokens of the text, including the paragraph breaks.
+    pub fn tokens(&self) -> &TokenStream<'a> {
+        &self.tokens
+    }
+
+    pub fn paragraphs(&self) -> &[Paragraph<'a>] {
+        &self.paragraphs
+    }
+
+    /// The tokens of the paragraph.
+    pub fn tokens_of(&self, paragraph: &Paragraph) -> &[Token<'a>] {
+        &self.tokens.tokens()[paragraph.tokens.clone()]
+    }
+
+    /// The paragraph containing the byte offset, an offset right after the end of a paragraph
+    /// (e.g. the insertion point of a comma) belongs to it as well.
+    pub fn paragraph_at(&self, offset: usize) -> Option<&Paragraph<'a>> {
+        self.paragraphs.iter().find(|a| a.span.start <= offset && offset <= a.span.end)
+    }
+
+    fn paragraph(text: &'a str, tokens: &[Token<'a>], range: Range<usize>) -> Paragraph<'a> {
+        let first = &tokens[range.start].position;
+        let last = &tokens[range.end - 1].position;
+
+        Paragraph {
+            text: &text[first.span.start..last.span.end],
+            span: first.span.start..last.span.end,
+            lines: (first.line, last.line),
+            tokens: range,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Document, TokenKind, Tokens};
+    use crate::segment::{ParagraphBreaks, Segmenter};
+
+    #[test]
+    fn paragraphs() {
+        let text = "Bevezetés\n\nAlma és körte,\namit vettünk.\n \n\nVége";
+        let document = Document::new(text);
+        let paragraphs = document.paragraphs();
+
+        assert_eq!(paragraphs.iter().map(|a| a.text).collect::<Vec<_>>(), vec!["Bevezetés", "Alma és körte,\namit vettünk.", "Vége"]);
+        assert_eq!(paragraphs[1].lines, (3, 4));
+        assert_eq!(document.tokens_of(&paragraphs[0]).len(), 1);
+        assert_eq!(document.paragraph_at(12).map(|a| a.lines), Some((3, 4)));
+        assert_eq!(document.paragraph_at(11), None);
+    }
+
+    #[test]
+    fn line_breaks() {
+        let text = "Alma\nKörte\n\nSzilva";
+        let segmenter = Segmenter::hungarian_default().with_paragraph_breaks(ParagraphBreaks::LineBreak);
+        let document = Document::from_tokens(text, Tokens::with_segmenter(text, &segmenter));
+
+        assert_eq!(document.paragraphs().iter().map(|a| a.text).collect::<Vec<_>>(), vec!["Alma", "Körte", "Szilva"]);
+        assert_eq!(document.tokens().iter().filter(|a| a.kind == TokenKind::ParagraphBreak).count(), 3);
+    }
+}
@@ -0,0 +1,21 @@
+use crate::model::Position;
+
+/// A change made by the autofix: the span of the position in the original text was replaced by the
+/// text. Inserted commas have an empty span, removed commas are replaced by an empty text.
+///
+/// The probability is the highest probability of the mistakes that suggested the change, and the
+/// rule identifiers of all of them are listed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Edit {
+    pub position: Position,
+    pub text: String,
+    pub prob: f64,
+    pub rule_ids: Vec<String>,
+}
+
+/// The corrected text and the list of changes that were made, ordered by their position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FixResult {
+    pub text: String,
+    pub edits: Vec<Edit>,
+}
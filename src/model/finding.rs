@@ -0,0 +1,52 @@
+use crate::model::{CommaAction, Mistake, Position};
+
+/// A possible missing (or superfluous) comma, which was reported by one or more detectors.
+///
+/// The probability is the combination of the probabilities of the contributing detectors, each of
+/// which is listed (by the name it was added with to the Analyzer) together with its own mistake.
+#[derive(Clone)]
+pub struct Finding {
+    pub row: usize,
+    pub col: usize,
+    pub prob: f64,
+    pub action: CommaAction,
+    pub insert_at: Option<Position>,
+    pub sources: Vec<(String, Mistake)>,
+}
+
+impl Finding {
+    pub fn new(row: usize, col: usize, name: &str, mistake: Mistake) -> Finding {
+        Finding {
+            row,
+            col,
+            prob: mistake.prob,
+            action: mistake.action,
+            insert_at: mistake.insert_at.clone(),
+            sources: vec![(String::from(name), mistake)],
+        }
+    }
+
+    /// Mistakes which know where the comma should be inserted are at the same place if their
+    /// insertion points are the same, the others if they were detected at the same row and column.
+    /// Missing and superfluous commas are never at the same place.
+    pub fn is_same_place(&self, row: usize, col: usize, mistake: &Mistake) -> bool {
+        if self.action != mistake.action {
+            return false;
+        }
+
+        match (&self.insert_at, &mistake.insert_at) {
+            (Some(a), Some(b)) => a.span.start == b.span.start,
+            (None, None) => self.row == row && self.col == col,
+            _ => false,
+        }
+    }
+
+    /// A key which is equal for the mistakes at the same place (see is_same_place), so that they
+    /// can be merged using a map.
+    pub(crate) fn place_key(row: usize, col: usize, mistake: &Mistake) -> (CommaAction, Option<usize>, usize, usize) {
+        match &mistake.insert_at {
+            Some(insert_at) => (mistake.action, Some(insert_at.span.start), 0, 0),
+            None => (mistake.action, None, row, col),
+        }
+    }
+}
@@ -0,0 +1,338 @@
+/// Determines which inflected forms of a word are generated (see `inflect`).
+///
+/// The classes include each other: Plural contains every form of Case, Possessive contains every
+/// form of Plural.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SuffixClass {
+    /// The word itself and its case forms (e.g. ami: amit, aminek, amiben, amivel...).
+    Case,
+    /// Case, and the plural form with its case forms (e.g. amely: amelyek, amelyeket...).
+    Plural,
+    /// Plural, and the third person singular possessive form with its case forms (e.g. alma:
+    /// almája, almájában...).
+    Possessive,
+}
+
+impl SuffixClass {
+    /// The name of the class in settings files: `case`, `plural` or `possessive`.
+    pub fn from_name(name: &str) -> Option<SuffixClass> {
+        match name {
+            "case" => Some(SuffixClass::Case),
+            "plural" => Some(SuffixClass::Plural),
+            "possessive" => Some(SuffixClass::Possessive),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Harmony {
+    Back,
+    FrontUnrounded,
+    FrontRounded,
+}
+
+/// The case suffixes in back, front unrounded and front rounded form. The accusative, the
+/// superessive and the suffixes starting with "v" are handled separately.
+const CASE_SUFFIXES: [[&str; 3]; 14] = [
+    ["nak", "nek", "nek"],
+    ["ban", "ben", "ben"],
+    ["ba", "be", "be"],
+    ["ból", "ből", "ből"],
+    ["ra", "re", "re"],
+    ["ról", "ről", "ről"],
+    ["hoz", "hez", "höz"],
+    ["tól", "től", "től"],
+    ["nál", "nél", "nél"],
+    ["ért", "ért", "ért"],
+    ["ig", "ig", "ig"],
+    ["ként", "ként", "ként"],
+    ["kor", "kor", "kor"],
+    ["ul", "ül", "ül"],
+];
+
+const DIGRAPHS: [&str; 8] = ["cs", "dz", "gy", "ly", "ny", "sz", "ty", "zs"];
+
+/// The words whose suffixes don't follow from their vowels: pronouns ending in "mi" or "ki" take
+/// front suffixes despite their back vowels (aminek, akinek), and a few words with only neutral
+/// vowels take back suffixes (hídnak, célnak).
+const HARMONY_EXCEPTIONS: [(&str, Harmony); 14] = [
+    ("ami", Harmony::FrontUnrounded),
+    ("aki", Harmony::FrontUnrounded),
+    ("bármi", Harmony::FrontUnrounded),
+    ("bárki", Harmony::FrontUnrounded),
+    ("akármi", Harmony::FrontUnrounded),
+    ("akárki", Harmony::FrontUnrounded),
+    ("valami", Harmony::FrontUnrounded),
+    ("valaki", Harmony::FrontUnrounded),
+    ("híd", Harmony::Back),
+    ("cél", Harmony::Back),
+    ("héj", Harmony::Back),
+    ("íj", Harmony::Back),
+    ("nyíl", Harmony::Back),
+    ("díj", Harmony::Back),
+];
+
+fn is_vowel(c: char) -> bool {
+    "aáeéiíoóöőuúüű".contains(c)
+}
+
+/// The neutral vowels don't decide the harmony if there is an other vowel before them (papír:
+/// papírban, kávé: kávénak).
+fn is_neutral(c: char) -> bool {
+    "éií".contains(c)
+}
+
+/// The last non-neutral vowel of the word determines which form of the suffixes is used, so a back
+/// vowel followed only by neutral ones still means back suffixes. Words with only neutral vowels
+/// take front suffixes (see HARMONY_EXCEPTIONS for the exceptions).
+fn harmony(word: &str) -> Harmony {
+    if let Some((_, harmony)) = HARMONY_EXCEPTIONS.iter().find(|(a, _)| *a == word) {
+        return *harmony;
+    }
+
+    match word.chars().rev().find(|c| is_vowel(*c) && !is_neutral(*c)) {
+        Some('a') | Some('á') | Some('o') | Some('ó') | Some('u') | Some('ú') => Harmony::Back,
+        _ => front_harmony(word),
+    }
+}
+
+/// The harmony of a word taking front suffixes, decided by its last front vowel.
+fn front_harmony(word: &str) -> Harmony {
+    match word.chars().rev().find(|c| is_vowel(*c) && !"aáoóuú".contains(*c)) {
+        Some('ö') | Some('ő') | Some('ü') | Some('ű') => Harmony::FrontRounded,
+        _ => Harmony::FrontUnrounded,
+    }
+}
+
+/// The harmony of a plural or possessive stem: it takes back suffixes if the word does, otherwise
+/// its own last front vowel decides (tükör: tükörök, tüköre).
+fn derived_harmony(word_harmony: Harmony, stem: &str) -> Harmony {
+    match word_harmony {
+        Harmony::Back => Harmony::Back,
+        _ => front_harmony(stem),
+    }
+}
+
+fn pick<'a>(harmony: Harmony, forms: &[&'a str; 3]) -> &'a str {
+    match harmony {
+        Harmony::Back => forms[0],
+        Harmony::FrontUnrounded => forms[1],
+        Harmony::FrontRounded => forms[2],
+    }
+}
+
+fn ends_with_vowel(word: &str) -> bool {
+    word.chars().last().map(is_vowel).unwrap_or(false)
+}
+
+/// A final short "a" or "e" is lengthened before suffixes (alma: almát, almában).
+fn lengthen(word: &str) -> String {
+    if let Some(stripped) = word.strip_suffix('a') {
+        format!("{}á", stripped)
+    } else if let Some(stripped) = word.strip_suffix('e') {
+        format!("{}é", stripped)
+    } else {
+        String::from(word)
+    }
+}
+
+/// The "v" of the instrumental and translative suffixes is assimilated to a final consonant, which
+/// is doubled (amely: amellyel, toll: tollal, hegy: heggyé).
+fn assimilated(word: &str, forms: &[&str; 3], harmony: Harmony) -> String {
+    let suffix = pick(harmony, forms);
+    if ends_with_vowel(word) {
+        return format!("{}v{}", lengthen(word), suffix);
+    }
+
+    if let Some(digraph) = DIGRAPHS.iter().find(|a| word.ends_with(*a)) {
+        let (stem, _) = word.split_at(word.len() - digraph.len());
+        if stem.ends_with(*digraph) {
+            return format!("{}{}", word, suffix);
+        }
+        let first = digraph.chars().next().unwrap();
+        return format!("{}{}{}{}", stem, first, digraph, suffix);
+    }
+
+    let last = match word.chars().last() {
+        Some(last) => last,
+        None => return String::new(),
+    };
+    if word.chars().rev().nth(1) == Some(last) {
+        format!("{}{}", word, suffix)
+    } else {
+        format!("{}{}{}", word, last, suffix)
+    }
+}
+
+/// Plural stems take "a" instead of "o" as the linking vowel of the accusative (almákat).
+fn case_forms(word: &str, harmony: Harmony, is_plural: bool, forms: &mut Vec<String>) {
+    let stem = lengthen(word);
+    let vowel_end = ends_with_vowel(word);
+
+    forms.push(String::from(word));
+    if vowel_end {
+        forms.push(format!("{}t", stem));
+        forms.push(format!("{}n", stem));
+    } else {
+        let accusative = if is_plural { ["at", "et", "et"] } else { ["ot", "et", "öt"] };
+        forms.push(format!("{}{}", word, pick(harmony, &accusative)));
+        forms.push(format!("{}{}", word, pick(harmony, &["on", "en", "ön"])));
+    }
+    for suffix in CASE_SUFFIXES.iter() {
+        forms.push(format!("{}{}", stem, pick(harmony, suffix)));
+    }
+    forms.push(assimilated(word, &["al", "el", "el"], harmony));
+    forms.push(assimilated(word, &["á", "é", "é"], harmony));
+}
+
+fn plural(word: &str, harmony: Harmony) -> String {
+    if ends_with_vowel(word) {
+        format!("{}k", lengthen(word))
+    } else {
+        format!("{}{}", word, pick(harmony, &["ok", "ek", "ök"]))
+    }
+}
+
+fn possessive(word: &str, harmony: Harmony) -> String {
+    if ends_with_vowel(word) {
+        format!("{}{}", lengthen(word), pick(harmony, &["ja", "je", "je"]))
+    } else {
+        format!("{}{}", word, pick(harmony, &["a", "e", "e"]))
+    }
+}
+
+/// Generates the inflected forms of the word using the suffixes of the given class, respecting
+/// vowel harmony. The first element is the word itself, there are no duplicates. Only the last word
+/// of a phrase is inflected ("nagy ház": "nagy házban"), an empty word has no forms. A phrase with
+/// a comma mark (see Phrase) is not inflected, its only form is itself.
+///
+/// Only the regular forms are generated: the results are meant to be used as trigger words, so a
+/// form that doesn't exist does no harm, but a missing one does.
+pub fn inflect(word: &str, class: SuffixClass) -> Vec<String> {
+    let word = word.trim();
+    if word.is_empty() {
+        return Vec::new();
+    }
+    if word.contains(',') {
+        return vec![String::from(word)];
+    }
+    if let Some((index, space)) = word.char_indices().rev().find(|(_, c)| c.is_whitespace()) {
+        let (prefix, head) = word.split_at(index + space.len_utf8());
+        return inflect(head, class).into_iter().map(|a| format!("{}{}", prefix, a)).collect();
+    }
+
+    let mut forms = Vec::new();
+    let harmony = harmony(word);
+
+    case_forms(word, harmony, false, &mut forms);
+    if class != SuffixClass::Case {
+        let plural = plural(word, harmony);
+        case_forms(&plural, derived_harmony(harmony, &plural), true, &mut forms);
+    }
+    if class == SuffixClass::Possessive {
+        let possessive = possessive(word, harmony);
+        case_forms(&possessive, derived_harmony(harmony, &possessive), false, &mut forms);
+    }
+
+    let mut unique: Vec<String> = Vec::with_capacity(forms.len());
+    for form in forms {
+        if !unique.contains(&form) {
+            unique.push(form);
+        }
+    }
+
+    unique
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{inflect, SuffixClass};
+
+    fn contains_all(forms: &[String], expected: &[&str]) -> bool {
+        expected.iter().all(|a| forms.iter().any(|b| b == a))
+    }
+
+    #[test]
+    fn front_vowel_pronoun() {
+        let forms = inflect("ami", SuffixClass::Case);
+
+        assert_eq!(forms[0], "ami");
+        assert!(contains_all(&forms, &["amit", "aminek", "amiben", "amibe", "amiből", "amire", "amiről", "amihez", "amivel", "amin", "amiért", "amiig"]));
+        assert!(!forms.iter().any(|a| a == "amik"));
+    }
+
+    #[test]
+    fn consonant_ending_and_digraph() {
+        let forms = inflect("amely", SuffixClass::Plural);
+
+        assert!(contains_all(&forms, &["amelyet", "amelyben", "amellyel", "amelyek", "amelyeket", "amelyekben", "amelyekkel", "amelyeknek"]));
+    }
+
+    #[test]
+    fn amelyik() {
+        let forms = inflect("amelyik", SuffixClass::Case);
+
+        assert!(contains_all(&forms, &["amelyikről", "amelyiket", "amelyikkel", "amelyikben"]));
+    }
+
+    #[test]
+    fn back_vowel_harmony() {
+        let forms = inflect("alma", SuffixClass::Possessive);
+
+        assert!(contains_all(&forms, &["almát", "almában", "almával", "almák", "almákat", "almája", "almájában", "almáról"]));
+    }
+
+    #[test]
+    fn front_rounded_harmony() {
+        let forms = inflect("tükör", SuffixClass::Case);
+
+        assert!(contains_all(&forms, &["tükörben", "tükörhöz", "tükörrel", "tükörtől", "tükörön"]));
+    }
+
+    #[test]
+    fn back_vowel_before_neutral_ones() {
+        let paper = inflect("papír", SuffixClass::Plural);
+        let coffee = inflect("kávé", SuffixClass::Case);
+
+        assert!(contains_all(&paper, &["papíron", "papírnak", "papírban", "papírral", "papírok", "papírokat"]));
+        assert!(!paper.iter().any(|a| a == "papírnek" || a == "papírben"));
+        assert!(contains_all(&coffee, &["kávét", "kávénak", "kávéval", "kávéban"]));
+        assert!(!coffee.iter().any(|a| a == "kávének"));
+    }
+
+    #[test]
+    fn harmony_exceptions() {
+        assert!(contains_all(&inflect("aki", SuffixClass::Plural), &["akinek", "akiben", "akik", "akiket", "akiknek"]));
+        assert!(contains_all(&inflect("híd", SuffixClass::Case), &["hídnak", "hídban", "híddal"]));
+        assert!(contains_all(&inflect("tükör", SuffixClass::Possessive), &["tükörök", "tükörökhöz", "tüköre", "tüköréhez"]));
+    }
+
+    #[test]
+    fn phrase_with_comma() {
+        assert_eq!(inflect("azért,", SuffixClass::Case), vec![String::from("azért,")]);
+    }
+
+    #[test]
+    fn class_names() {
+        assert_eq!(SuffixClass::from_name("case"), Some(SuffixClass::Case));
+        assert_eq!(SuffixClass::from_name("plural"), Some(SuffixClass::Plural));
+        assert_eq!(SuffixClass::from_name("possessive"), Some(SuffixClass::Possessive));
+        assert_eq!(SuffixClass::from_name("verb"), None);
+    }
+
+    #[test]
+    fn empty_word() {
+        assert!(inflect("", SuffixClass::Possessive).is_empty());
+        assert!(inflect("  ", SuffixClass::Case).is_empty());
+    }
+
+    #[test]
+    fn phrase_head_word() {
+        let forms = inflect("nagy  ház", SuffixClass::Plural);
+
+        assert_eq!(forms[0], "nagy  ház");
+        assert!(contains_all(&forms, &["nagy  házon", "nagy  házban", "nagy  házzal", "nagy  házért"]));
+        assert!(!forms.iter().any(|a| a.starts_with("nagyot")));
+    }
+}
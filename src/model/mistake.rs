@@ -1,13 +1,72 @@
+use crate::model::Position;
+
 #[derive(Clone)]
 pub enum Msg {
     Static(&'static str),
     Dynamic(String),
 }
 
+/// The kind of detector (rule) which reported a mistake.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DetectorKind {
+    /// A word which is usually preceded by a comma (NaiveDetector).
+    BeforeWord,
+    /// A word which is usually followed by a comma (NaiveForwardDetector).
+    AfterWord,
+    /// A pair of words which usually has a comma between them (PairDetector).
+    Pair,
+    /// A word which usually implies a comma somewhere in the sentence (TypicalDetector).
+    Typical,
+    /// A comma which usually shouldn't be there (SuperfluousDetector).
+    Superfluous,
+    /// A mistake that wasn't created by one of the detectors of this crate.
+    Custom,
+}
+
+impl DetectorKind {
+    /// A short, stable identifier, it is used as the prefix of the rule identifiers.
+    pub fn id(&self) -> &'static str {
+        match self {
+            DetectorKind::BeforeWord => "before",
+            DetectorKind::AfterWord => "after",
+            DetectorKind::Pair => "pair",
+            DetectorKind::Typical => "typical",
+            DetectorKind::Superfluous => "superfluous",
+            DetectorKind::Custom => "custom",
+        }
+    }
+}
+
+/// What should be done to correct the mistake.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CommaAction {
+    /// A comma is missing, it should be inserted at `insert_at` (if it is known).
+    Insert,
+    /// The comma at `position` is superfluous, it should be removed.
+    Remove,
+}
+
+/// A possible mistake in the text.
+///
+/// Besides the probability and the (Hungarian) message, it contains which rule was triggered
+/// (`rule_id` is the detector kind and the trigger words joined by colons, e.g. `before:hogy` or
+/// `pair:ha:akkor`), where the mistake was detected, whether a comma should be inserted or removed
+/// and where the comma should be inserted (if the detector knows it).
 #[derive(Clone)]
 pub struct Mistake {
     pub prob: f64,
-    pub msg: Msg
+    pub msg: Msg,
+    /// The position of the token at which the mistake was detected.
+    pub position: Position,
+    pub kind: DetectorKind,
+    pub rule_id: String,
+    pub triggers: Vec<String>,
+    pub action: CommaAction,
+    /// The (empty) place where the comma should be inserted.
+    pub insert_at: Option<Position>,
+    /// The places where the comma may be inserted, the most probable one first, if the detector
+    /// can't tell exactly. If it isn't empty, its first element is `insert_at`.
+    pub candidates: Vec<Position>,
 }
 
 impl Mistake {
@@ -15,6 +74,13 @@ impl Mistake {
         Mistake {
             prob,
             msg: Msg::Static(msg),
+            position: Position::default(),
+            kind: DetectorKind::Custom,
+            rule_id: String::from(DetectorKind::Custom.id()),
+            triggers: Vec::new(),
+            action: CommaAction::Insert,
+            insert_at: None,
+            candidates: Vec::new(),
         }
     }
 
@@ -22,6 +88,13 @@ impl Mistake {
         Mistake {
             prob,
             msg: Msg::Dynamic(msg),
+            position: Position::default(),
+            kind: DetectorKind::Custom,
+            rule_id: String::from(DetectorKind::Custom.id()),
+            triggers: Vec::new(),
+            action: CommaAction::Insert,
+            insert_at: None,
+            candidates: Vec::new(),
         }
     }
 
@@ -29,9 +102,46 @@ impl Mistake {
         Mistake {
             prob,
             msg: Msg::Dynamic(String::from(msg)),
+            position: Position::default(),
+            kind: DetectorKind::Custom,
+            rule_id: String::from(DetectorKind::Custom.id()),
+            triggers: Vec::new(),
+            action: CommaAction::Insert,
+            insert_at: None,
+            candidates: Vec::new(),
         }
     }
 
+    pub fn with_position(mut self, position: Position) -> Mistake {
+        self.position = position;
+        self
+    }
+
+    /// Sets the kind of the detector and the trigger words, the rule identifier is built from them.
+    pub fn with_rule(mut self, kind: DetectorKind, triggers: &[&str]) -> Mistake {
+        self.kind = kind;
+        self.triggers = triggers.iter().map(|a| String::from(*a)).collect();
+        self.rule_id = std::iter::once(kind.id()).chain(triggers.iter().copied()).collect::<Vec<&str>>().join(":");
+        self
+    }
+
+    pub fn with_action(mut self, action: CommaAction) -> Mistake {
+        self.action = action;
+        self
+    }
+
+    pub fn with_insertion_point(mut self, insert_at: Position) -> Mistake {
+        self.insert_at = Some(insert_at);
+        self
+    }
+
+    /// Sets the ranked candidate insertion points, the first one becomes the insertion point.
+    pub fn with_candidates(mut self, candidates: Vec<Position>) -> Mistake {
+        self.insert_at = candidates.first().cloned();
+        self.candidates = candidates;
+        self
+    }
+
     pub fn get_str(&self) -> &str {
         match &self.msg {
             Msg::Dynamic(msg) => {
@@ -1,12 +1,48 @@
 mod typical_settings;
+mod abbreviation_settings;
 mod pair_settings;
+mod pair_options;
+mod pair_part;
 mod naive_settings;
+mod superfluous_settings;
+mod settings_error;
+mod settings_parser;
+mod validation;
 mod mistake;
+mod inflection;
+mod context_exception;
+mod normalization;
+mod phrase;
+mod finding;
+mod combination_rule;
+mod edit;
 mod text_token;
+mod token;
+mod token_kind;
+mod document;
+mod position;
 
 pub use naive_settings::NaiveSettings;
 pub use pair_settings::PairSettings;
+pub use pair_options::PairOptions;
+pub use pair_part::PairPart;
 pub use typical_settings::TypicalSettings;
+pub use superfluous_settings::SuperfluousSettings;
+pub use abbreviation_settings::AbbreviationSettings;
+pub use settings_error::{SettingsError, SettingsErrorKind};
+pub use validation::ValidationError;
 
-pub use text_token::PlainTextToken;
-pub use mistake::Mistake;
+pub use inflection::{inflect, SuffixClass};
+pub use context_exception::{ContextException, ContextSide};
+pub use normalization::{CaseMode, Normalization};
+pub use phrase::Phrase;
+pub use token::{Token, Tokens, TokenStream};
+pub use token_kind::TokenKind;
+pub use document::{Document, Paragraph};
+pub use position::Position;
+pub use mistake::{CommaAction, DetectorKind, Mistake};
+pub use finding::Finding;
+pub use combination_rule::CombinationRule;
+pub use edit::{Edit, FixResult};
+
+pub(crate) use text_token::lex;
@@ -1,31 +1,300 @@
+use std::collections::HashSet;
+
+use crate::model::{inflect, ContextException, Normalization, SettingsError, SuffixClass, ValidationError};
+use crate::model::settings_parser::{settings_rows, strict, SettingsRow};
+use crate::model::validation::{check_exceptions, check_phrases, check_words};
+
 /// Contains the words that are used in the NaiveDetector.
 ///
-/// Loads words from files.
+/// Loads words from files. Every row of the file has the following format:
+/// `word;probability;suffix_class;exceptions`, the suffix class and the exceptions are optional,
+/// everything after a `#` is a comment.
+///
+/// If the suffix class (`case`, `plural` or `possessive`, see SuffixClass) is given, the word is a
+/// stem: its inflected forms are added with the same probability, unless they are in the file as
+/// separate words.
+///
+/// The word can also be a phrase of multiple words, with the place of the comma marked in it (e.g.
+/// `annak ellenére, hogy;0.9`, see Phrase).
+///
+/// The exceptions are whitespace separated context exceptions, which override the probability of
+/// the word if it is preceded or followed by a given word (e.g. `ha;0.55;;<csak=0 <még=0.1`, see
+/// ContextException). The inflected forms of a stem get the same exceptions. The `exceptions` field
+/// is either empty (no exceptions at all) or it has an element for every word.
 #[derive(Clone)]
 pub struct NaiveSettings {
     pub words: Vec<String>,
     pub probs: Vec<f64>,
+    pub exceptions: Vec<Vec<ContextException>>,
 }
 
 impl NaiveSettings {
-    pub fn new_from_string(content: String) -> NaiveSettings {
-        let rows = content.split('\n');
+    /// The bundled Hungarian dictionary of words that are usually preceded by a comma, for the
+    /// NaiveDetector (see `dictionaries/before.csv` for the probabilities and their sources).
+    pub fn hungarian_default() -> NaiveSettings {
+        NaiveSettings::new_from_string(String::from(include_str!("../../dictionaries/before.csv")))
+            .expect("the bundled dictionary is valid")
+    }
+
+    /// The bundled Hungarian dictionary of words that are usually followed by a comma, for the
+    /// NaiveForwardDetector (see `dictionaries/after.csv` for the probabilities and their sources).
+    pub fn hungarian_forward_default() -> NaiveSettings {
+        NaiveSettings::new_from_string(String::from(include_str!("../../dictionaries/after.csv")))
+            .expect("the bundled dictionary is valid")
+    }
+
+    /// Loads the settings, fails on the first row that is not valid.
+    pub fn new_from_string(content: String) -> Result<NaiveSettings, SettingsError> {
+        strict(NaiveSettings::new_from_string_lenient(content))
+    }
+
+    /// Loads the settings, skipping every row that is not valid. The problems of these rows are
+    /// returned alongside the settings.
+    pub fn new_from_string_lenient(content: String) -> (NaiveSettings, Vec<SettingsError>) {
+        let mut settings = NaiveSettings {
+            words: Vec::new(),
+            probs: Vec::new(),
+            exceptions: Vec::new(),
+        };
+        let mut errors = Vec::new();
+        let mut stems = Vec::new();
+
+        for row in settings_rows(&content) {
+            match settings.add_row(&row) {
+                Ok(Some(stem)) => stems.push(stem),
+                Ok(None) => {}
+                Err(error) => errors.push(error),
+            }
+        }
+
+        // The inflected forms are added last, so that the words in the file take precedence
+        for (index, class) in stems {
+            settings.add_inflected_forms(index, class);
+        }
+
+        (settings, errors)
+    }
+
+    /// Adds the stem and its inflected forms (see SuffixClass) with the given probability. Forms
+    /// that are already in the settings (in any case) are skipped, an empty stem is ignored.
+    pub fn add_inflected(&mut self, stem: &str, prob: f64, class: SuffixClass) {
+        if stem.trim().is_empty() {
+            return;
+        }
+
+        let index = match self.position_of(stem) {
+            Some(index) => index,
+            None => {
+                self.push(String::from(stem), prob, Vec::new());
+                self.words.len() - 1
+            }
+        };
+        self.add_inflected_forms(index, class);
+    }
+
+    /// The index of the word, compared after the default normalization (see Normalization).
+    fn position_of(&self, word: &str) -> Option<usize> {
+        let normalization = Normalization::default();
+        let word = normalization.normalize(word);
 
-        let mut words = Vec::new();
-        let mut probs = Vec::new();
+        self.words.iter().position(|a| normalization.normalize(a) == word)
+    }
 
-        for row in rows {
-            let cols = row.split(';').collect::<Vec<&str>>();
+    fn add_inflected_forms(&mut self, index: usize, class: SuffixClass) {
+        let prob = self.probs[index];
+        let exceptions = self.exceptions.get(index).cloned().unwrap_or_default();
+        let normalization = Normalization::default();
+        let mut known = self.words.iter().map(|a| normalization.normalize(a)).collect::<HashSet<String>>();
 
-            if cols.len() > 1 {
-                words.push(String::from(cols[0]));
-                probs.push(cols[1].trim().parse::<f64>().unwrap());
+        for form in inflect(&self.words[index], class) {
+            if known.insert(normalization.normalize(&form)) {
+                self.push(form, prob, exceptions.clone());
             }
         }
+    }
 
-        NaiveSettings {
-            words,
-            probs,
+    /// Adds a word, the exceptions are only stored if there is an element for every word.
+    fn push(&mut self, word: String, prob: f64, exceptions: Vec<ContextException>) {
+        if self.exceptions.len() == self.words.len() {
+            self.exceptions.push(exceptions);
         }
+        self.words.push(word);
+        self.probs.push(prob);
+    }
+
+    /// The context exceptions of the word at the given index.
+    pub fn exceptions_of(&self, index: usize) -> &[ContextException] {
+        self.exceptions.get(index).map(|a| a.as_slice()).unwrap_or(&[])
+    }
+
+    /// Checks that every word has a probability, the words are unique, non-empty, valid phrases
+    /// and the probabilities (of the exceptions too) are in the 0..=1 range.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_words("words", &self.words, &self.probs)?;
+        check_phrases(&self.words)?;
+        check_exceptions(&self.words, &self.exceptions)
+    }
+
+    /// Returns the index of the word and its suffix class, if it is a stem.
+    fn add_row(&mut self, row: &SettingsRow) -> Result<Option<(usize, SuffixClass)>, SettingsError> {
+        row.expect_columns(2, 4)?;
+        let word = row.phrase(0)?;
+        let prob = row.prob(1)?;
+        let class = row.suffix_class(2)?;
+        let exceptions = row.exceptions(3)?;
+        row.check_duplicate(0, &self.words)?;
+
+        self.push(word, prob, exceptions);
+
+        Ok(class.map(|a| (self.words.len() - 1, a)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{ContextException, ContextSide, NaiveSettings, SettingsError, SettingsErrorKind, SuffixClass, ValidationError};
+
+    #[test]
+    fn valid_file() {
+        let settings = NaiveSettings::new_from_string(String::from("hogy;1.0\r\n\nami; 0.8\n")).unwrap();
+
+        assert_eq!(settings.words, vec![String::from("hogy"), String::from("ami")]);
+        assert_eq!(settings.probs, vec![1.0, 0.8]);
+    }
+
+    #[test]
+    fn invalid_probability() {
+        let error = NaiveSettings::new_from_string(String::from("hogy;1.0\nami;0,8")).err().unwrap();
+
+        assert_eq!(error, SettingsError::new(2, 5, "0,8", SettingsErrorKind::InvalidProbability));
+    }
+
+    #[test]
+    fn probability_out_of_range() {
+        let error = NaiveSettings::new_from_string(String::from("hogy;1.5")).err().unwrap();
+
+        assert_eq!(error, SettingsError::new(1, 6, "1.5", SettingsErrorKind::ProbabilityOutOfRange));
+    }
+
+    #[test]
+    fn empty_word() {
+        let error = NaiveSettings::new_from_string(String::from(" ;0.5")).err().unwrap();
+
+        assert_eq!(error.kind, SettingsErrorKind::EmptyWord);
+        assert_eq!((error.line, error.column), (1, 1));
+    }
+
+    #[test]
+    fn duplicate_entry() {
+        let error = NaiveSettings::new_from_string(String::from("hogy;1.0\nhogy;0.5")).err().unwrap();
+
+        assert_eq!(error, SettingsError::new(2, 1, "hogy", SettingsErrorKind::DuplicateEntry));
+    }
+
+    #[test]
+    fn wrong_column_count() {
+        let error = NaiveSettings::new_from_string(String::from("hogy")).err().unwrap();
+
+        assert_eq!(error.kind, SettingsErrorKind::WrongColumnCount { min: 2, max: 4, found: 1 });
+    }
+
+    #[test]
+    fn comments() {
+        let settings = NaiveSettings::new_from_string(String::from("# header\nhogy;1.0 # source\n  # indented\n")).unwrap();
+
+        assert_eq!(settings.words, vec![String::from("hogy")]);
+    }
+
+    #[test]
+    fn inflected_stems() {
+        let settings = NaiveSettings::new_from_string(String::from("ami;0.9;case\namit;0.5\namely;0.8;plural")).unwrap();
+
+        assert_eq!(settings.words[..3], [String::from("ami"), String::from("amit"), String::from("amely")]);
+        let prob = |word: &str| settings.probs[settings.words.iter().position(|a| a == word).unwrap()];
+        assert_eq!(prob("amit"), 0.5);
+        assert_eq!(prob("aminek"), 0.9);
+        assert_eq!(prob("amelyeket"), 0.8);
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn inflected_empty_stem_and_phrase() {
+        let mut settings = NaiveSettings::new_from_string(String::from("abban az;0.8;case")).unwrap();
+        settings.add_inflected("", 0.5, SuffixClass::Case);
+        settings.add_inflected(" ", 0.5, SuffixClass::Case);
+
+        assert!(settings.words.iter().all(|a| a.starts_with("abban az")));
+        assert!(settings.words.contains(&String::from("abban azzal")));
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn inflected_phrase_with_comma() {
+        let error = NaiveSettings::new_from_string(String::from("hogy;0.9\nazért,;0.8;case")).err().unwrap();
+
+        assert_eq!(error, SettingsError::new(2, 1, "azért,", SettingsErrorKind::InflectedComma));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn duplicates_in_other_case() {
+        let error = NaiveSettings::new_from_string(String::from("hogy;0.9\nHogy;0.8")).err().unwrap();
+        let mut settings = NaiveSettings::new_from_string(String::from("Amit;0.5")).unwrap();
+        settings.add_inflected("ami", 0.9, SuffixClass::Case);
+
+        assert_eq!(error, SettingsError::new(2, 1, "Hogy", SettingsErrorKind::DuplicateEntry));
+        assert_eq!(NaiveSettings { words: vec![String::from("hogy"), String::from("HOGY")], probs: vec![0.9, 0.8], exceptions: Vec::new() }.validate(), Err(ValidationError::DuplicateEntry { index: 1, word: String::from("HOGY") }));
+        assert!(!settings.words.contains(&String::from("amit")));
+        assert!(settings.validate().is_ok());
+    }
+
+    #[test]
+    fn unknown_suffix_class() {
+        let error = NaiveSettings::new_from_string(String::from("ami;0.9;verb")).err().unwrap();
+
+        assert_eq!(error, SettingsError::new(1, 9, "verb", SettingsErrorKind::UnknownSuffixClass));
+    }
+
+    #[test]
+    fn lenient_skips_invalid_rows() {
+        let (settings, errors) = NaiveSettings::new_from_string_lenient(String::from("hogy;1.0\nami;x\naki;2\nha;0.5"));
+
+        assert_eq!(settings.words, vec![String::from("hogy"), String::from("ha")]);
+        assert_eq!(settings.probs, vec![1.0, 0.5]);
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].line, 2);
+        assert_eq!(errors[1].line, 3);
+    }
+
+    #[test]
+    fn phrases() {
+        let settings = NaiveSettings::new_from_string(String::from("annak ellenére ,hogy;0.9\nmivel  hogy;0.5")).unwrap();
+
+        assert_eq!(settings.words, vec![String::from("annak ellenére, hogy"), String::from("mivel hogy")]);
+    }
+
+    #[test]
+    fn invalid_phrase() {
+        let error = NaiveSettings::new_from_string(String::from("hogy;1.0\nminél, annál, inkább;0.5")).err().unwrap();
+
+        assert_eq!(error, SettingsError::new(2, 1, "minél, annál, inkább", SettingsErrorKind::InvalidPhrase));
+    }
+
+    #[test]
+    fn context_exceptions() {
+        let settings = NaiveSettings::new_from_string(String::from("ha;0.55;;<csak=0 <még=0.1\nami;0.9;case;>pedig\nhogy;0.9")).unwrap();
+        let amit = settings.words.iter().position(|a| a == "amit").unwrap();
+
+        assert_eq!(settings.exceptions.len(), settings.words.len());
+        assert_eq!(settings.exceptions[0], vec![ContextException::new(ContextSide::Preceding, "csak", 0.0), ContextException::new(ContextSide::Preceding, "még", 0.1)]);
+        assert_eq!(settings.exceptions_of(amit), &[ContextException::new(ContextSide::Following, "pedig", 0.0)]);
+        assert!(settings.exceptions_of(2).is_empty());
+    }
+
+    #[test]
+    fn invalid_exception() {
+        let errors = NaiveSettings::new_from_string_lenient(String::from("ha;0.5;;csak=0\nha;0.5;;<csak=2")).1;
+
+        assert_eq!(errors[0], SettingsError::new(1, 9, "csak=0", SettingsErrorKind::InvalidException));
+        assert_eq!(errors[1], SettingsError::new(2, 9, "<csak=2", SettingsErrorKind::ProbabilityOutOfRange));
+    }
+}
@@ -0,0 +1,128 @@
+/// How the case of the words is handled before they are compared.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum CaseMode {
+    /// The words are compared as they are.
+    Preserve,
+    /// Both the dictionary words and the tokens are converted to lowercase.
+    #[default]
+    Lowercase,
+    /// Unicode case folding: lowercase plus the special foldings (e.g. "ß" -> "ss"), so that words
+    /// which only differ in case are always equal.
+    Fold,
+}
+
+/// Converts the dictionary words and the tokens into a common form before matching them.
+///
+/// Every detector uses the same normalization for its dictionary and for the text, so the
+/// dictionaries don't have to be lowercase. By default the words are lowercased and the
+/// diacritics are kept.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub struct Normalization {
+    pub case: CaseMode,
+    /// If true, the diacritics are removed ("mielőtt" and "mielott" are the same word). Useful for
+    /// text typed without accents.
+    pub fold_diacritics: bool,
+}
+
+impl Normalization {
+    pub fn new(case: CaseMode, fold_diacritics: bool) -> Normalization {
+        Normalization {
+            case,
+            fold_diacritics,
+        }
+    }
+
+    pub fn normalize(&self, word: &str) -> String {
+        let word = match self.case {
+            CaseMode::Preserve => String::from(word),
+            CaseMode::Lowercase => word.to_lowercase(),
+            CaseMode::Fold => word.chars().flat_map(char::to_lowercase).map(fold_case).collect(),
+        };
+
+        if self.fold_diacritics {
+            word.chars().map(fold_diacritic).collect()
+        } else {
+            word
+        }
+    }
+
+    /// Normalizes every word of a dictionary.
+    pub fn normalize_all(&self, words: &[String]) -> Vec<String> {
+        words.iter().map(|a| self.normalize(a)).collect()
+    }
+}
+
+/// The foldings which are not covered by lowercasing.
+fn fold_case(c: char) -> String {
+    match c {
+        'ß' => String::from("ss"),
+        'ς' => String::from("σ"),
+        'ſ' => String::from("s"),
+        'ﬀ' => String::from("ff"),
+        'ﬁ' => String::from("fi"),
+        'ﬂ' => String::from("fl"),
+        _ => c.to_string(),
+    }
+}
+
+fn fold_diacritic(c: char) -> char {
+    match c {
+        'á' | 'à' | 'â' | 'ä' | 'ã' | 'å' | 'ă' | 'ą' => 'a',
+        'Á' | 'À' | 'Â' | 'Ä' | 'Ã' | 'Å' | 'Ă' | 'Ą' => 'A',
+        'é' | 'è' | 'ê' | 'ë' | 'ě' | 'ę' => 'e',
+        'É' | 'È' | 'Ê' | 'Ë' | 'Ě' | 'Ę' => 'E',
+        'í' | 'ì' | 'î' | 'ï' => 'i',
+        'Í' | 'Ì' | 'Î' | 'Ï' => 'I',
+        'ó' | 'ò' | 'ô' | 'ö' | 'õ' | 'ő' | 'ø' => 'o',
+        'Ó' | 'Ò' | 'Ô' | 'Ö' | 'Õ' | 'Ő' | 'Ø' => 'O',
+        'ú' | 'ù' | 'û' | 'ü' | 'ű' | 'ů' => 'u',
+        'Ú' | 'Ù' | 'Û' | 'Ü' | 'Ű' | 'Ů' => 'U',
+        'ý' | 'ÿ' => 'y',
+        'Ý' => 'Y',
+        'ç' | 'č' | 'ć' => 'c',
+        'Ç' | 'Č' | 'Ć' => 'C',
+        'ñ' | 'ň' | 'ń' => 'n',
+        'Ñ' | 'Ň' | 'Ń' => 'N',
+        'š' | 'ś' => 's',
+        'Š' | 'Ś' => 'S',
+        'ž' | 'ź' | 'ż' => 'z',
+        'Ž' | 'Ź' | 'Ż' => 'Z',
+        'ř' => 'r',
+        'Ř' => 'R',
+        'ł' => 'l',
+        'Ł' => 'L',
+        'ď' => 'd',
+        'Ď' => 'D',
+        'ť' => 't',
+        'Ť' => 'T',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{CaseMode, Normalization};
+
+    #[test]
+    fn default_lowercases() {
+        assert_eq!(Normalization::default().normalize("AKI"), "aki");
+        assert_eq!(Normalization::default().normalize("Mielőtt"), "mielőtt");
+    }
+
+    #[test]
+    fn preserve() {
+        assert_eq!(Normalization::new(CaseMode::Preserve, false).normalize("Ami"), "Ami");
+    }
+
+    #[test]
+    fn case_folding() {
+        assert_eq!(Normalization::new(CaseMode::Fold, false).normalize("STRAßE"), "strasse");
+        assert_eq!(Normalization::new(CaseMode::Fold, false).normalize("Ő"), "ő");
+    }
+
+    #[test]
+    fn diacritic_folding() {
+        assert_eq!(Normalization::new(CaseMode::Lowercase, true).normalize("Mielőtt"), "mielott");
+        assert_eq!(Normalization::new(CaseMode::Preserve, true).normalize("ŰRÜGY"), "URUGY");
+    }
+}
@@ -0,0 +1,52 @@
+/// The rules of a pair of the PairSettings, besides its words.
+///
+/// In the settings files they are written as whitespace separated options: `distance=8` (the
+/// second word has to be at most 8 tokens after the first one), `reversible` (the second word can
+/// come first, e.g. "akkor ... ha") and `nested` (the pair can contain an other instance of
+/// itself, e.g. "ha ... ha ... akkor ... akkor").
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct PairOptions {
+    /// The maximal number of tokens after the first word (line breaks excluded) where the second
+    /// word is still looked for. None means the end of the sentence.
+    pub max_distance: Option<usize>,
+    /// The words of the pair can be in reversed order.
+    pub reversible: bool,
+    /// A repeated first word starts an inner pair, instead of restarting the pair.
+    pub nested: bool,
+}
+
+impl PairOptions {
+    /// Returns the option that is not valid if there is one.
+    pub fn parse(text: &str) -> Result<PairOptions, &str> {
+        let mut options = PairOptions::default();
+
+        for option in text.split_whitespace() {
+            match option {
+                "reversible" => options.reversible = true,
+                "nested" => options.nested = true,
+                _ => {
+                    let distance = option.strip_prefix("distance=")
+                        .and_then(|a| a.parse::<usize>().ok())
+                        .filter(|a| *a > 0)
+                        .ok_or(option)?;
+                    options.max_distance = Some(distance);
+                }
+            }
+        }
+
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::PairOptions;
+
+    #[test]
+    fn parse() {
+        assert_eq!(PairOptions::parse(""), Ok(PairOptions::default()));
+        assert_eq!(PairOptions::parse("nested  distance=5 reversible"), Ok(PairOptions { max_distance: Some(5), reversible: true, nested: true }));
+        assert_eq!(PairOptions::parse("distance=0"), Err("distance=0"));
+        assert_eq!(PairOptions::parse("reversed"), Err("reversed"));
+    }
+}
@@ -0,0 +1,20 @@
+/// A part of a pair after its second word, e.g. "is" in "nemcsak ... hanem ... is".
+///
+/// In the settings files the parts are separated by `|` in the column of the second words, and a
+/// `~` before a part means that no comma is expected before it: `nemcsak;0.95;hanem|~is`. The words
+/// of a part are alternatives, just like the second words.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PairPart {
+    pub words: Vec<String>,
+    /// A comma is expected before the part.
+    pub comma: bool,
+}
+
+impl PairPart {
+    pub fn new(words: Vec<String>, comma: bool) -> PairPart {
+        PairPart {
+            words,
+            comma,
+        }
+    }
+}
@@ -1,37 +1,184 @@
-/// Contains the words that are used in the NaiveDetector.
+use crate::model::{PairOptions, PairPart, SettingsError, ValidationError};
+use crate::model::settings_parser::{settings_rows, strict, SettingsRow};
+use crate::model::validation::{check_length, check_phrases, check_words};
+
+/// Contains the words that are used in the PairDetector.
+///
+/// Loads words from files. Every row of the file has the following format:
+/// `first_word;probability;second_word another_second_word;options`, the options are optional (see
+/// PairOptions), everything after a `#` is a comment.
 ///
-/// Loads words from files.
+/// The words can be anchors of multiple words: in the first column the words are separated by
+/// spaces, among the second words by `_` (e.g. `nem csak;0.9;hanem`, `bár;0.8;de_mégis`). A pair can
+/// have more than two parts, the later parts are separated by `|` (e.g. `se;0.8;se|se`), a `~` before
+/// a part means that it doesn't need a comma before it (e.g. `nemcsak;0.95;hanem|~is`, see PairPart).
+///
+/// The `options` and the `further_parts` fields are either empty (every pair uses the default
+/// options, no pair has more than two parts) or they have an element for every pair.
 #[derive(Clone)]
 pub struct PairSettings {
     pub first_words: Vec<String>,
     pub second_words: Vec<Vec<String>>,
     pub probs: Vec<f64>,
+    pub options: Vec<PairOptions>,
+    pub further_parts: Vec<Vec<PairPart>>,
 }
 
-impl PairSettings {
-    pub fn new_from_string(content: String) -> PairSettings {
-        let rows = content.split('\n');
+static DEFAULT_OPTIONS: PairOptions = PairOptions { max_distance: None, reversible: false, nested: false };
 
-        let mut first_words = Vec::new();
-        let mut second_words = Vec::new();
-        let mut probs = Vec::new();
+impl PairSettings {
+    /// The bundled Hungarian dictionary of word pairs (see `dictionaries/pair.csv` for the
+    /// probabilities and their sources).
+    pub fn hungarian_default() -> PairSettings {
+        PairSettings::new_from_string(String::from(include_str!("../../dictionaries/pair.csv")))
+            .expect("the bundled dictionary is valid")
+    }
 
-        for row in rows {
-            let cols = row.split(';').collect::<Vec<&str>>();
+    /// Loads the settings, fails on the first row that is not valid.
+    pub fn new_from_string(content: String) -> Result<PairSettings, SettingsError> {
+        strict(PairSettings::new_from_string_lenient(content))
+    }
 
-            if cols.len() > 2 {
-                let seconds = cols[2].split(' ').map(String::from).collect();
+    /// Loads the settings, skipping every row that is not valid. The problems of these rows are
+    /// returned alongside the settings.
+    pub fn new_from_string_lenient(content: String) -> (PairSettings, Vec<SettingsError>) {
+        let mut settings = PairSettings {
+            first_words: Vec::new(),
+            second_words: Vec::new(),
+            probs: Vec::new(),
+            options: Vec::new(),
+            further_parts: Vec::new(),
+        };
+        let mut errors = Vec::new();
 
-                first_words.push(String::from(cols[0]));
-                second_words.push(seconds);
-                probs.push(cols[1].trim().parse::<f64>().unwrap());
+        for row in settings_rows(&content) {
+            if let Err(error) = settings.add_row(&row) {
+                errors.push(error);
             }
         }
 
-        PairSettings {
-            first_words,
-            second_words,
-            probs,
+        (settings, errors)
+    }
+
+    /// The options of the pair at the given index.
+    pub fn options_of(&self, index: usize) -> &PairOptions {
+        self.options.get(index).unwrap_or(&DEFAULT_OPTIONS)
+    }
+
+    /// The parts of the pair at the given index after the second word.
+    pub fn further_parts_of(&self, index: usize) -> &[PairPart] {
+        self.further_parts.get(index).map(|a| a.as_slice()).unwrap_or(&[])
+    }
+
+    /// Checks that every first word has a probability and a non-empty list of second words (and
+    /// words in the further parts), the first words are unique and non-empty and the probabilities
+    /// are in the 0..=1 range.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_words("first_words", &self.first_words, &self.probs)?;
+        check_phrases(&self.first_words)?;
+        check_length("second_words", self.first_words.len(), self.second_words.len())?;
+        if !self.options.is_empty() {
+            check_length("options", self.first_words.len(), self.options.len())?;
         }
+        if !self.further_parts.is_empty() {
+            check_length("further_parts", self.first_words.len(), self.further_parts.len())?;
+        }
+
+        for (index, seconds) in self.second_words.iter().enumerate() {
+            if seconds.is_empty() || seconds.iter().any(|a| a.trim().is_empty()) {
+                return Err(ValidationError::EmptyWord { field: "second_words", index });
+            }
+            check_phrases(seconds)?;
+        }
+        for (index, parts) in self.further_parts.iter().enumerate() {
+            if parts.iter().any(|a| a.words.is_empty() || a.words.iter().any(|b| b.trim().is_empty())) {
+                return Err(ValidationError::EmptyWord { field: "further_parts", index });
+            }
+            for part in parts.iter() {
+                check_phrases(&part.words)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn add_row(&mut self, row: &SettingsRow) -> Result<(), SettingsError> {
+        row.expect_columns(3, 4)?;
+        let first_word = row.anchor(0)?;
+        let prob = row.prob(1)?;
+        let (seconds, further_parts) = row.parts(2)?;
+        let options = row.pair_options(3)?;
+        row.check_duplicate(0, &self.first_words)?;
+
+        self.first_words.push(first_word);
+        self.second_words.push(seconds);
+        self.probs.push(prob);
+        self.options.push(options);
+        self.further_parts.push(further_parts);
+
+        Ok(())
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{PairOptions, PairPart, PairSettings, SettingsError, SettingsErrorKind};
+
+    #[test]
+    fn valid_file() {
+        let settings = PairSettings::new_from_string(String::from("ha;0.9;akkor  aztán\nmind;1;mind\n")).unwrap();
+
+        assert_eq!(settings.first_words, vec![String::from("ha"), String::from("mind")]);
+        assert_eq!(settings.second_words, vec![vec![String::from("akkor"), String::from("aztán")], vec![String::from("mind")]]);
+        assert_eq!(settings.probs, vec![0.9, 1.0]);
+    }
+
+    #[test]
+    fn empty_second_words() {
+        let error = PairSettings::new_from_string(String::from("ha;0.9; ")).err().unwrap();
+
+        assert_eq!(error.kind, SettingsErrorKind::EmptyWord);
+        assert_eq!((error.line, error.column), (1, 8));
+    }
+
+    #[test]
+    fn wrong_column_count() {
+        let (settings, errors) = PairSettings::new_from_string_lenient(String::from("ha;0.9\nmind;1;mind"));
+
+        assert_eq!(settings.first_words, vec![String::from("mind")]);
+        assert_eq!(errors[0].kind, SettingsErrorKind::WrongColumnCount { min: 3, max: 4, found: 2 });
+    }
+
+    #[test]
+    fn options() {
+        let settings = PairSettings::new_from_string(String::from("ha;0.9;akkor;reversible distance=12\nmind;1;mind")).unwrap();
+
+        assert_eq!(settings.options_of(0), &PairOptions { max_distance: Some(12), reversible: true, nested: false });
+        assert_eq!(settings.options_of(1), &PairOptions::default());
+    }
+
+    #[test]
+    fn invalid_option() {
+        let error = PairSettings::new_from_string(String::from("ha;0.9;akkor;nested distance=x")).err().unwrap();
+
+        assert_eq!(error, SettingsError::new(1, 14, "distance=x", SettingsErrorKind::InvalidOption));
+    }
+
+    #[test]
+    fn parts() {
+        let settings = PairSettings::new_from_string(String::from("nem_csak;0.9;hanem | ~is\nbár;0.8;de_mégis mégis")).unwrap();
+
+        assert_eq!(settings.first_words, vec![String::from("nem csak"), String::from("bár")]);
+        assert_eq!(settings.second_words, vec![vec![String::from("hanem")], vec![String::from("de mégis"), String::from("mégis")]]);
+        assert_eq!(settings.further_parts_of(0), &[PairPart::new(vec![String::from("is")], false)]);
+        assert!(settings.further_parts_of(1).is_empty());
+    }
+
+    #[test]
+    fn invalid_parts() {
+        let errors = PairSettings::new_from_string_lenient(String::from("se;0.8;se||se\nnem;0.5;~hanem")).1;
+
+        assert_eq!(errors[0].kind, SettingsErrorKind::EmptyWord);
+        assert_eq!(errors[1], SettingsError::new(2, 9, "~hanem", SettingsErrorKind::InvalidPart));
+    }
+}
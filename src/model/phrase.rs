@@ -0,0 +1,97 @@
+use std::fmt;
+
+/// A dictionary entry of one or more words, with the place of the comma marked in it.
+///
+/// In the settings files a phrase is written as its words separated by spaces, and a `,` marks
+/// where the comma goes: `annak ellenére, hogy`, `azért, mert`, `még akkor is, ha`. A single word
+/// is a phrase of one word. If there is no comma in the phrase, the detector decides where the
+/// comma goes: the NaiveDetector expects it before the phrase, the NaiveForwardDetector after it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Phrase {
+    pub words: Vec<String>,
+    /// The index of the word the comma has to precede. It is equal to the number of words if the
+    /// comma has to follow the phrase.
+    pub comma_at: Option<usize>,
+}
+
+impl Phrase {
+    /// Returns None if the text doesn't contain any words, or it contains more than one comma.
+    pub fn parse(text: &str) -> Option<Phrase> {
+        let mut words = Vec::new();
+        let mut comma_at = None;
+
+        for part in text.replace(',', " , ").split_whitespace() {
+            if part == "," {
+                if comma_at.is_some() {
+                    return None;
+                }
+                comma_at = Some(words.len());
+            } else {
+                words.push(String::from(part));
+            }
+        }
+
+        if words.is_empty() {
+            return None;
+        }
+
+        Some(Phrase {
+            words,
+            comma_at,
+        })
+    }
+
+    pub fn is_single_word(&self) -> bool {
+        self.words.len() == 1 && self.comma_at.is_none()
+    }
+}
+
+impl fmt::Display for Phrase {
+    /// The canonical form of the phrase: the words separated by a space, the comma right after the
+    /// word it follows.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.comma_at == Some(0) {
+            write!(f, ", ")?;
+        }
+        for (index, word) in self.words.iter().enumerate() {
+            if index > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", word)?;
+            if self.comma_at == Some(index + 1) {
+                write!(f, ",")?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::Phrase;
+
+    #[test]
+    fn single_word() {
+        let phrase = Phrase::parse(" hogy ").unwrap();
+
+        assert_eq!(phrase.words, vec![String::from("hogy")]);
+        assert_eq!(phrase.comma_at, None);
+        assert!(phrase.is_single_word());
+    }
+
+    #[test]
+    fn comma_marked() {
+        let phrase = Phrase::parse("annak ellenére ,hogy").unwrap();
+
+        assert_eq!(phrase.words, vec![String::from("annak"), String::from("ellenére"), String::from("hogy")]);
+        assert_eq!(phrase.comma_at, Some(2));
+        assert_eq!(phrase.to_string(), "annak ellenére, hogy");
+    }
+
+    #[test]
+    fn invalid_phrases() {
+        assert_eq!(Phrase::parse(" , "), None);
+        assert_eq!(Phrase::parse("minél, annál, inkább"), None);
+    }
+}
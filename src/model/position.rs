@@ -0,0 +1,17 @@
+use std::ops::Range;
+
+/// The place of a token (or a mistake) in the text.
+///
+/// The span is a byte range, the line and the columns are 1-based. The column is given both in
+/// characters and in UTF-16 code units (which is what JavaScript and many editors use).
+///
+/// The span indexes the text given to the detector (e.g. to detect_errors or
+/// detect_errors_in_stream). If a text is given row by row (see Detector::detect_errors_in_row),
+/// the span is relative to the row, while the line counts the previous rows as well.
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Position {
+    pub span: Range<usize>,
+    pub line: usize,
+    pub col: usize,
+    pub utf16_col: usize,
+}
@@ -0,0 +1,84 @@
+use std::error::Error;
+use std::fmt;
+
+/// The reason why a row of a settings file couldn't be loaded.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SettingsErrorKind {
+    /// The probability column is not a floating-point number.
+    InvalidProbability,
+    /// The probability is a number, but it is not in the 0..=1 range.
+    ProbabilityOutOfRange,
+    /// A word column is empty.
+    EmptyWord,
+    /// The word was already defined in a previous row.
+    DuplicateEntry,
+    /// The row doesn't have the number of columns the format requires (at least `min`, at most
+    /// `max`).
+    WrongColumnCount { min: usize, max: usize, found: usize },
+    /// The suffix class column doesn't contain one of the known classes.
+    UnknownSuffixClass,
+    /// A suffix class is given for a phrase with a comma in it, which can't be inflected.
+    InflectedComma,
+    /// The phrase has more than one comma in it (see Phrase).
+    InvalidPhrase,
+    /// The context exception is not in the `<word=probability` or `>word=probability` format (see
+    /// ContextException).
+    InvalidException,
+    /// The options column of a pair contains an unknown option (see PairOptions).
+    InvalidOption,
+    /// The second words of a pair are marked with `~` (only the later parts can be, see PairPart).
+    InvalidPart,
+}
+
+/// Describes a row of a settings file which couldn't be loaded.
+///
+/// Line and column are 1-based, the column points to the first character of the offending text.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SettingsError {
+    pub line: usize,
+    pub column: usize,
+    pub text: String,
+    pub kind: SettingsErrorKind,
+}
+
+impl SettingsError {
+    pub fn new(line: usize, column: usize, text: &str, kind: SettingsErrorKind) -> SettingsError {
+        SettingsError {
+            line,
+            column,
+            text: String::from(text),
+            kind,
+        }
+    }
+}
+
+impl fmt::Display for SettingsErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SettingsErrorKind::InvalidProbability => write!(f, "the probability is not a number"),
+            SettingsErrorKind::ProbabilityOutOfRange => write!(f, "the probability is not between 0 and 1"),
+            SettingsErrorKind::EmptyWord => write!(f, "the word is empty"),
+            SettingsErrorKind::DuplicateEntry => write!(f, "the word is already defined"),
+            SettingsErrorKind::WrongColumnCount { min, max, found } if min == max => {
+                write!(f, "expected {} columns, found {}", min, found)
+            }
+            SettingsErrorKind::WrongColumnCount { min, max, found } => {
+                write!(f, "expected {} to {} columns, found {}", min, max, found)
+            }
+            SettingsErrorKind::UnknownSuffixClass => write!(f, "unknown suffix class"),
+            SettingsErrorKind::InflectedComma => write!(f, "a phrase with a comma can't be inflected"),
+            SettingsErrorKind::InvalidPhrase => write!(f, "the phrase contains more than one comma"),
+            SettingsErrorKind::InvalidException => write!(f, "the context exception is not valid"),
+            SettingsErrorKind::InvalidOption => write!(f, "unknown option"),
+            SettingsErrorKind::InvalidPart => write!(f, "the second words always need a comma before them"),
+        }
+    }
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {} (\"{}\")", self.line, self.column, self.kind, self.text)
+    }
+}
+
+impl Error for SettingsError {}
@@ -0,0 +1,199 @@
+use crate::model::{ContextException, Normalization, PairOptions, PairPart, Phrase, SettingsError, SettingsErrorKind, SuffixClass};
+
+/// A non-empty row of a settings file split into its columns.
+///
+/// Every column is stored together with its 1-based character column in the row.
+pub(crate) struct SettingsRow<'a> {
+    pub line: usize,
+    pub cols: Vec<(usize, &'a str)>,
+}
+
+impl<'a> SettingsRow<'a> {
+    fn new(line: usize, row: &'a str) -> SettingsRow<'a> {
+        let mut cols = Vec::new();
+        let mut column = 1;
+
+        for col in row.split(';') {
+            cols.push((column, col));
+            column += col.chars().count() + 1;
+        }
+
+        SettingsRow {
+            line,
+            cols,
+        }
+    }
+
+    fn error(&self, col: usize, text: &str, kind: SettingsErrorKind) -> SettingsError {
+        SettingsError::new(self.line, self.cols[col].0, text, kind)
+    }
+
+    /// Checks that the row has at least `min` and at most `max` columns.
+    pub fn expect_columns(&self, min: usize, max: usize) -> Result<(), SettingsError> {
+        if self.cols.len() < min || self.cols.len() > max {
+            let line = self.cols.iter().map(|(_, a)| *a).collect::<Vec<&str>>().join(";");
+            return Err(SettingsError::new(self.line, 1, &line, SettingsErrorKind::WrongColumnCount { min, max, found: self.cols.len() }));
+        }
+
+        Ok(())
+    }
+
+    /// Returns the trimmed content of the given column, or None if the row doesn't have that column
+    /// or it is empty.
+    pub fn optional(&self, col: usize) -> Option<&'a str> {
+        self.cols.get(col).map(|(_, a)| a.trim()).filter(|a| !a.is_empty())
+    }
+
+    /// Returns the suffix class in the given column (if there is one). The phrase in the first
+    /// column can't have a comma in it then, since it is inflected (see inflect).
+    pub fn suffix_class(&self, col: usize) -> Result<Option<SuffixClass>, SettingsError> {
+        match self.optional(col) {
+            Some(_) if self.cols[0].1.contains(',') => Err(self.error(0, self.cols[0].1.trim(), SettingsErrorKind::InflectedComma)),
+            Some(name) => SuffixClass::from_name(name)
+                .map(Some)
+                .ok_or_else(|| self.error(col, name, SettingsErrorKind::UnknownSuffixClass)),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the whitespace separated context exceptions in the given column (see
+    /// ContextException), the column is optional.
+    pub fn exceptions(&self, col: usize) -> Result<Vec<ContextException>, SettingsError> {
+        let mut exceptions = Vec::new();
+
+        for text in self.optional(col).unwrap_or("").split_whitespace() {
+            let exception = ContextException::parse(text)
+                .ok_or_else(|| self.error(col, text, SettingsErrorKind::InvalidException))?;
+            if !(0.0..=1.0).contains(&exception.prob) {
+                return Err(self.error(col, text, SettingsErrorKind::ProbabilityOutOfRange));
+            }
+            exceptions.push(exception);
+        }
+
+        Ok(exceptions)
+    }
+
+    /// Returns the pair options in the given column (see PairOptions), the column is optional.
+    pub fn pair_options(&self, col: usize) -> Result<PairOptions, SettingsError> {
+        PairOptions::parse(self.optional(col).unwrap_or(""))
+            .map_err(|option| self.error(col, option, SettingsErrorKind::InvalidOption))
+    }
+
+    /// Returns whether the given column contains the flag, the column is optional, but it can't
+    /// contain anything else.
+    pub fn flag(&self, col: usize, name: &str) -> Result<bool, SettingsError> {
+        match self.optional(col) {
+            Some(text) if text == name => Ok(true),
+            Some(text) => Err(self.error(col, text, SettingsErrorKind::InvalidOption)),
+            None => Ok(false),
+        }
+    }
+
+    /// Returns the trimmed word in the given column, it can't be empty.
+    pub fn word(&self, col: usize) -> Result<String, SettingsError> {
+        let word = self.cols[col].1.trim();
+        if word.is_empty() {
+            return Err(self.error(col, self.cols[col].1, SettingsErrorKind::EmptyWord));
+        }
+
+        Ok(String::from(word))
+    }
+
+    /// Returns the phrase in the given column in its canonical form (see Phrase), it can't be empty
+    /// or contain more than one comma.
+    pub fn phrase(&self, col: usize) -> Result<String, SettingsError> {
+        let word = self.word(col)?;
+        match Phrase::parse(&word) {
+            Some(phrase) => Ok(phrase.to_string()),
+            None => Err(self.error(col, &word, SettingsErrorKind::InvalidPhrase)),
+        }
+    }
+
+    /// Returns the trimmed anchor in the given column, it can't be empty. An anchor is one or more
+    /// words, which are separated by spaces or `_`, in the result they are separated by a space.
+    pub fn anchor(&self, col: usize) -> Result<String, SettingsError> {
+        self.word(col).map(|a| to_anchor(&a))
+    }
+
+    /// Returns the `|` separated parts of the given column, each of them is a list of whitespace
+    /// separated anchors (where the words of an anchor are joined by `_`), there has to be at least
+    /// one anchor in every part. The first part is returned as a list of anchors, the others as
+    /// PairParts (which can be marked with `~`).
+    pub fn parts(&self, col: usize) -> Result<(Vec<String>, Vec<PairPart>), SettingsError> {
+        let mut parts = Vec::new();
+
+        for part in self.cols[col].1.split('|') {
+            let trimmed = part.trim();
+            let (comma, words) = match trimmed.strip_prefix('~') {
+                Some(words) => (false, words),
+                None => (true, trimmed),
+            };
+            let words = words.split_whitespace().map(to_anchor).collect::<Vec<String>>();
+            if words.is_empty() {
+                return Err(self.error(col, part, SettingsErrorKind::EmptyWord));
+            }
+            parts.push(PairPart::new(words, comma));
+        }
+
+        let second = parts.remove(0);
+        if !second.comma {
+            return Err(self.error(col, self.cols[col].1.trim(), SettingsErrorKind::InvalidPart));
+        }
+
+        Ok((second.words, parts))
+    }
+
+    /// Returns the probability in the given column, which has to be in the 0..=1 range.
+    pub fn prob(&self, col: usize) -> Result<f64, SettingsError> {
+        let text = self.cols[col].1.trim();
+        let prob = text.parse::<f64>()
+            .map_err(|_| self.error(col, text, SettingsErrorKind::InvalidProbability))?;
+
+        if !(0.0..=1.0).contains(&prob) {
+            return Err(self.error(col, text, SettingsErrorKind::ProbabilityOutOfRange));
+        }
+
+        Ok(prob)
+    }
+
+    /// Returns an error if the word in the given column is already in the list. The words are
+    /// compared after the default normalization (see Normalization), since they would match the
+    /// same tokens.
+    pub fn check_duplicate(&self, col: usize, words: &[String]) -> Result<(), SettingsError> {
+        let normalization = Normalization::default();
+        let text = self.cols[col].1.trim();
+        let word = normalization.normalize(text);
+        let canonical = Phrase::parse(&word).map(|a| a.to_string());
+        if words.iter().map(|a| normalization.normalize(a)).any(|a| a == word || Some(&a) == canonical.as_ref()) {
+            return Err(self.error(col, text, SettingsErrorKind::DuplicateEntry));
+        }
+
+        Ok(())
+    }
+}
+
+fn to_anchor(text: &str) -> String {
+    text.split(|a: char| a == '_' || a.is_whitespace())
+        .filter(|a| !a.is_empty())
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// Splits the content of a settings file into rows, skipping the empty ones. Everything after a `#`
+/// is a comment.
+pub(crate) fn settings_rows(content: &str) -> impl Iterator<Item = SettingsRow<'_>> {
+    content.split('\n')
+        .enumerate()
+        .map(|(index, row)| (index + 1, row.split('#').next().unwrap_or("").trim_end_matches('\r')))
+        .filter(|(_, row)| !row.trim().is_empty())
+        .map(|(line, row)| SettingsRow::new(line, row))
+}
+
+/// Returns the first error (if there is one), this is how the strict loaders fail.
+pub(crate) fn strict<T>((settings, mut errors): (T, Vec<SettingsError>)) -> Result<T, SettingsError> {
+    if errors.is_empty() {
+        Ok(settings)
+    } else {
+        Err(errors.remove(0))
+    }
+}
@@ -0,0 +1,104 @@
+use crate::model::{SettingsError, ValidationError};
+use crate::model::settings_parser::{settings_rows, strict, SettingsRow};
+use crate::model::validation::{check_length, check_words};
+
+/// Contains the words that are used in the SuperfluousDetector: a comma before these words is
+/// usually superfluous.
+///
+/// Loads words from files. Every row of the file has the following format:
+/// `word;probability;parts`, where `parts` is optional: it marks the conjunctions (e.g. "és") which
+/// only make the comma superfluous if they join parts of a sentence, not clauses. Everything after
+/// a `#` is a comment.
+#[derive(Clone)]
+pub struct SuperfluousSettings {
+    pub words: Vec<String>,
+    pub probs: Vec<f64>,
+    /// Empty, or for every word whether it has to join parts of a sentence (see above).
+    pub joins_parts: Vec<bool>,
+}
+
+impl SuperfluousSettings {
+    /// The bundled Hungarian dictionary of words that are usually not preceded by a comma (see
+    /// `dictionaries/superfluous.csv` for the probabilities and their sources).
+    pub fn hungarian_default() -> SuperfluousSettings {
+        SuperfluousSettings::new_from_string(String::from(include_str!("../../dictionaries/superfluous.csv")))
+            .expect("the bundled dictionary is valid")
+    }
+
+    /// Loads the settings, fails on the first row that is not valid.
+    pub fn new_from_string(content: String) -> Result<SuperfluousSettings, SettingsError> {
+        strict(SuperfluousSettings::new_from_string_lenient(content))
+    }
+
+    /// Loads the settings, skipping every row that is not valid. The problems of these rows are
+    /// returned alongside the settings.
+    pub fn new_from_string_lenient(content: String) -> (SuperfluousSettings, Vec<SettingsError>) {
+        let mut settings = SuperfluousSettings {
+            words: Vec::new(),
+            probs: Vec::new(),
+            joins_parts: Vec::new(),
+        };
+        let mut errors = Vec::new();
+
+        for row in settings_rows(&content) {
+            if let Err(error) = settings.add_row(&row) {
+                errors.push(error);
+            }
+        }
+
+        (settings, errors)
+    }
+
+    /// True if the comma before the word is only superfluous if the word joins parts of a sentence.
+    pub fn joins_parts_of(&self, index: usize) -> bool {
+        self.joins_parts.get(index).copied().unwrap_or(false)
+    }
+
+    /// Checks that every word has a probability, the words are unique and non-empty and the
+    /// probabilities are in the 0..=1 range.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_words("words", &self.words, &self.probs)?;
+        if !self.joins_parts.is_empty() {
+            check_length("joins_parts", self.words.len(), self.joins_parts.len())?;
+        }
+
+        Ok(())
+    }
+
+    fn add_row(&mut self, row: &SettingsRow) -> Result<(), SettingsError> {
+        row.expect_columns(2, 3)?;
+        let word = row.word(0)?;
+        let prob = row.prob(1)?;
+        let joins_parts = row.flag(2, "parts")?;
+        row.check_duplicate(0, &self.words)?;
+
+        self.words.push(word);
+        self.probs.push(prob);
+        self.joins_parts.push(joins_parts);
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{SettingsErrorKind, SuperfluousSettings, ValidationError};
+
+    #[test]
+    fn load() {
+        let settings = SuperfluousSettings::new_from_string(String::from("stb;0.9\nés;0.3;parts  # comment")).unwrap();
+
+        assert_eq!(settings.words, vec![String::from("stb"), String::from("és")]);
+        assert_eq!(settings.joins_parts, vec![false, true]);
+
+        let error = SuperfluousSettings::new_from_string(String::from("és;0.3;clause")).err().unwrap();
+        assert_eq!(error.kind, SettingsErrorKind::InvalidOption);
+    }
+
+    #[test]
+    fn validate() {
+        let settings = SuperfluousSettings { words: vec![String::from("stb")], probs: vec![0.9], joins_parts: vec![true, false] };
+
+        assert!(matches!(settings.validate(), Err(ValidationError::LengthMismatch { field: "joins_parts", .. })));
+    }
+}
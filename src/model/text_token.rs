@@ -1,7 +1,13 @@
-use logos::Logos;
+use std::iter::Peekable;
+use std::ops::Range;
 
+use logos::{Logos, SpannedIter};
+
+use crate::model::TokenKind;
+
+/// The tokens of the built-in tokenizer (see Tokens::new), the detectors only see their TokenKind.
 #[derive(Logos, Debug, PartialEq, Clone)]
-pub enum PlainTextToken {
+pub(crate) enum PlainTextToken {
     // Tokens can be literal strings, of any length.
     #[regex("[0-9]+(,[0-9]+)?")]
     Number,
@@ -9,14 +15,40 @@ pub enum PlainTextToken {
     #[regex("[.?!]")]
     EndOfSentence,
 
+    // A number followed by a period, e.g. "3. fejezet" (dates are made of these, see lex)
+    #[regex(r"[0-9]+\.")]
+    Ordinal,
+
     #[regex("[;,]")]
     Comma,
 
+    #[token(":")]
+    Colon,
+
+    // A hyphen surrounded by spaces is a dash too (see lex)
+    #[regex("[–—]")]
+    Dash,
+
+    #[regex(r"[(\[]")]
+    OpenParen,
+
+    #[regex(r"[)\]]")]
+    CloseParen,
+
+    // Whether it opens or closes the quote is decided by its neighbours (see lex)
+    #[regex("[„“”‚‘’«»‹›\"]")]
+    Quote,
+
+    // Three periods are an ellipsis too (see lex)
+    #[token("…")]
+    Ellipsis,
+
     #[token("\n")]
     NewLine,
 
-    // Or regular expressions.
-    #[regex("[A-Za-zÀ-ÖØ-öø-ÿ]+")]
+    // Any sequence of letters (the whole Hungarian alphabet including ő and ű, as well as foreign
+    // letters and combining marks), hyphenated compounds are kept as a single word.
+    #[regex(r"[\p{L}\p{M}]+(-[\p{L}\p{M}]+)*")]
     Text,
 
     // Logos requires one token variant to handle errors,
@@ -26,4 +58,330 @@ pub enum PlainTextToken {
     // or any other matches we wish to skip.
     #[regex(r"[ \t\f\r]+", logos::skip)]
     Error,
-}
\ No newline at end of file
+}
+
+impl From<PlainTextToken> for TokenKind {
+    fn from(token: PlainTextToken) -> TokenKind {
+        match token {
+            PlainTextToken::Number => TokenKind::Number,
+            PlainTextToken::EndOfSentence => TokenKind::EndOfSentence,
+            PlainTextToken::Comma => TokenKind::Comma,
+            PlainTextToken::NewLine => TokenKind::NewLine,
+            PlainTextToken::Text => TokenKind::Text,
+            PlainTextToken::Ordinal => TokenKind::Ordinal,
+            PlainTextToken::Colon => TokenKind::Colon,
+            PlainTextToken::Dash => TokenKind::Dash,
+            PlainTextToken::OpenParen => TokenKind::OpenParen,
+            PlainTextToken::CloseParen => TokenKind::CloseParen,
+            PlainTextToken::Quote => TokenKind::ClosingQuote,
+            PlainTextToken::Ellipsis => TokenKind::Ellipsis,
+            PlainTextToken::Error => TokenKind::Other,
+        }
+    }
+}
+
+const MONTHS: &[&str] = &[
+    "január", "február", "március", "április", "május", "június", "július", "augusztus",
+    "szeptember", "október", "november", "december", "jan", "febr", "márc", "ápr", "máj", "jún",
+    "júl", "aug", "szept", "okt", "nov", "dec",
+];
+
+/// Splits the text into tokens. The dates ("2026. október 18.", "2026. okt. 18-án", "2026.10.18."),
+/// Roman numerals ("II."), initials ("J.") and ellipses ("...") are single tokens. They are put
+/// together from the tokens of the lexer, since the lexer can't step back if the beginning of a date
+/// is not followed by the rest of it.
+///
+/// A quotation mark opens a quote if it is at the beginning of the text or after a space, a
+/// parenthesis, a dash or an other opening quotation mark, except for "„" and "‚" (which always open
+/// a quote) and "”" and "’" (which always close it). A hyphen surrounded by spaces is a dash.
+pub(crate) fn lex(text: &str) -> impl Iterator<Item = (TokenKind, Range<usize>)> + '_ {
+    Compounds {
+        source: text,
+        tokens: PlainTextToken::lexer(text).spanned().peekable(),
+    }
+}
+
+struct Compounds<'a> {
+    source: &'a str,
+    tokens: Peekable<SpannedIter<'a, PlainTextToken>>,
+}
+
+impl<'a> Compounds<'a> {
+    /// Skips the tokens which start before the end.
+    fn skip_until(&mut self, end: usize) {
+        while self.tokens.peek().is_some_and(|(_, a)| a.start < end) {
+            self.tokens.next();
+        }
+    }
+}
+
+impl<'a> Iterator for Compounds<'a> {
+    type Item = (TokenKind, Range<usize>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (token, span) = self.tokens.next()?;
+
+        if token == PlainTextToken::Ordinal && span.len() == 5 {
+            if let Some(length) = date_length(&self.source[span.start..]) {
+                let end = span.start + length;
+                self.skip_until(end);
+
+                return Some((TokenKind::Date, span.start..end));
+            }
+        }
+
+        if token == PlainTextToken::EndOfSentence {
+            let periods = self.source[span.start..].chars().take_while(|a| *a == '.').count();
+            if periods >= 3 {
+                self.skip_until(span.start + periods);
+
+                return Some((TokenKind::Ellipsis, span.start..span.start + periods));
+            }
+        }
+
+        if token == PlainTextToken::Quote {
+            let kind = match &self.source[span.clone()] {
+                "„" | "‚" => TokenKind::OpeningQuote,
+                "”" | "’" => TokenKind::ClosingQuote,
+                _ if self.source[..span.start].chars().next_back().map_or(true, opens_quote) => TokenKind::OpeningQuote,
+                _ => TokenKind::ClosingQuote,
+            };
+
+            return Some((kind, span));
+        }
+
+        if token == PlainTextToken::Error && &self.source[span.clone()] == "-" {
+            let before = self.source[..span.start].chars().next_back();
+            let after = self.source[span.end..].chars().next();
+            if before.map_or(true, char::is_whitespace) && after.map_or(true, char::is_whitespace) {
+                return Some((TokenKind::Dash, span));
+            }
+        }
+
+        if token == PlainTextToken::Text && self.source[span.end..].starts_with('.') {
+            let word = &self.source[span.clone()];
+            let kind = if word.chars().count() == 1 && word.chars().all(char::is_uppercase) {
+                Some(TokenKind::Initial)
+            } else if word.len() > 1 && is_roman_numeral(word) {
+                Some(TokenKind::RomanNumeral)
+            } else {
+                None
+            };
+
+            if let Some(kind) = kind {
+                self.skip_until(span.end + 1);
+
+                return Some((kind, span.start..span.end + 1));
+            }
+        }
+
+        Some((token.into(), span))
+    }
+}
+
+/// True if a quotation mark after the character opens a quote.
+fn opens_quote(c: char) -> bool {
+    c.is_whitespace() || "([–—-„‚“‘«»‹›\"".contains(c)
+}
+
+/// True if the word is a well-formed Roman numeral (from I to MMMCMXCIX), not just any word made of
+/// the letters of the numerals ("CIVIL").
+fn is_roman_numeral(word: &str) -> bool {
+    let thousands = word.chars().take_while(|a| *a == 'M').count();
+    if word.is_empty() || thousands > 3 {
+        return false;
+    }
+
+    let mut rest = &word[thousands..];
+    for (one, five, ten) in [('C', 'D', 'M'), ('X', 'L', 'C'), ('I', 'V', 'X')].iter() {
+        rest = strip_numeral_place(rest, *one, *five, *ten);
+    }
+
+    rest.is_empty()
+}
+
+/// Strips one decimal place of a Roman numeral from the beginning of the text: nine (e.g. "IX"),
+/// four ("IV") or an optional five followed by at most three ones ("VIII").
+fn strip_numeral_place(text: &str, one: char, five: char, ten: char) -> &str {
+    let mut chars = text.chars();
+    if chars.next() == Some(one) && matches!(chars.next(), Some(a) if a == five || a == ten) {
+        return &text[2..];
+    }
+
+    let rest = text.strip_prefix(five).unwrap_or(text);
+    let ones = rest.chars().take(3).take_while(|a| *a == one).count();
+
+    &rest[ones..]
+}
+
+/// Returns the length of the date at the beginning of the text (which starts with a year and a
+/// period): a month (a number with a period or the name of the month) and a day (a number with a
+/// period or with a suffix) have to follow.
+fn date_length(text: &str) -> Option<usize> {
+    let mut rest = text[5..].trim_start_matches(' ');
+
+    rest = match number_length(rest) {
+        Some(length) => rest[length..].strip_prefix('.')?,
+        None => {
+            let month = MONTHS.iter().find(|a| rest.starts_with(*a) && !rest[a.len()..].starts_with(char::is_alphabetic))?;
+            let rest = &rest[month.len()..];
+            rest.strip_prefix('.').unwrap_or(rest)
+        }
+    };
+    rest = rest.trim_start_matches(' ');
+
+    let length = number_length(rest)?;
+    rest = match rest[length..].strip_prefix('-') {
+        Some(suffix) if suffix.starts_with(char::is_alphabetic) => suffix.trim_start_matches(char::is_alphabetic),
+        _ => rest[length..].strip_prefix('.')?,
+    };
+
+    Some(text.len() - rest.len())
+}
+
+/// The length of the one or two digit number at the beginning of the text.
+fn number_length(text: &str) -> Option<usize> {
+    let length = text.chars().take_while(char::is_ascii_digit).count();
+
+    Some(length).filter(|a| (1..=2).contains(a))
+}
+
+#[cfg(test)]
+mod tests {
+    use logos::Logos;
+
+    use crate::model::TokenKind;
+    use crate::model::text_token::PlainTextToken;
+
+    fn lex(text: &str) -> Vec<(PlainTextToken, &str)> {
+        let mut tokens = PlainTextToken::lexer(text);
+        let mut result = Vec::new();
+        while let Some(token) = tokens.next() {
+            result.push((token, tokens.slice()));
+        }
+
+        result
+    }
+
+    fn compounds(text: &str) -> Vec<(TokenKind, &str)> {
+        super::lex(text)
+            .map(|(kind, span)| (kind, &text[span]))
+            .collect()
+    }
+
+    #[test]
+    fn ordinals_and_initials() {
+        assert_eq!(compounds("A 2. pontban II. Rákóczi és J. K. Rowling 3,5. MIT."), vec![
+            (TokenKind::Text, "A"),
+            (TokenKind::Ordinal, "2."),
+            (TokenKind::Text, "pontban"),
+            (TokenKind::RomanNumeral, "II."),
+            (TokenKind::Text, "Rákóczi"),
+            (TokenKind::Text, "és"),
+            (TokenKind::Initial, "J."),
+            (TokenKind::Initial, "K."),
+            (TokenKind::Text, "Rowling"),
+            (TokenKind::Number, "3,5"),
+            (TokenKind::EndOfSentence, "."),
+            (TokenKind::Text, "MIT"),
+            (TokenKind::EndOfSentence, "."),
+        ]);
+    }
+
+    #[test]
+    fn capitalized_words_are_not_numerals() {
+        assert_eq!(compounds("XIV. Lajos CIVIL. DILI. MMMM."), vec![
+            (TokenKind::RomanNumeral, "XIV."),
+            (TokenKind::Text, "Lajos"),
+            (TokenKind::Text, "CIVIL"),
+            (TokenKind::EndOfSentence, "."),
+            (TokenKind::Text, "DILI"),
+            (TokenKind::EndOfSentence, "."),
+            (TokenKind::Text, "MMMM"),
+            (TokenKind::EndOfSentence, "."),
+        ]);
+        assert!(["II", "IV", "IX", "XL", "XC", "CD", "CM", "MCMXCIX", "MMMCMXCIX"].iter().all(|a| super::is_roman_numeral(a)));
+        assert!(!["IIII", "VV", "IC", "XM", "VX", "CIVIL", "DILI"].iter().any(|a| super::is_roman_numeral(a)));
+    }
+
+    #[test]
+    fn punctuation() {
+        assert_eq!(compounds("Azt mondta: „Jövök” – (talán)... Vagy… \"nem\" - bor- és"), vec![
+            (TokenKind::Text, "Azt"),
+            (TokenKind::Text, "mondta"),
+            (TokenKind::Colon, ":"),
+            (TokenKind::OpeningQuote, "„"),
+            (TokenKind::Text, "Jövök"),
+            (TokenKind::ClosingQuote, "”"),
+            (TokenKind::Dash, "–"),
+            (TokenKind::OpenParen, "("),
+            (TokenKind::Text, "talán"),
+            (TokenKind::CloseParen, ")"),
+            (TokenKind::Ellipsis, "..."),
+            (TokenKind::Text, "Vagy"),
+            (TokenKind::Ellipsis, "…"),
+            (TokenKind::OpeningQuote, "\""),
+            (TokenKind::Text, "nem"),
+            (TokenKind::ClosingQuote, "\""),
+            (TokenKind::Dash, "-"),
+            (TokenKind::Text, "bor"),
+            (TokenKind::Other, "-"),
+            (TokenKind::Text, "és"),
+        ]);
+    }
+
+    #[test]
+    fn dates() {
+        fn dates(text: &str) -> Vec<(TokenKind, &str)> {
+            compounds(text).into_iter().filter(|(kind, _)| *kind == TokenKind::Date).collect()
+        }
+
+        assert_eq!(dates("2026. október 18. van"), vec![(TokenKind::Date, "2026. október 18.")]);
+        assert_eq!(dates("2026. okt. 18-án jön"), vec![(TokenKind::Date, "2026. okt. 18-án")]);
+        assert_eq!(dates("2026.10.18. és 2026. 10. 18."), vec![(TokenKind::Date, "2026.10.18."), (TokenKind::Date, "2026. 10. 18.")]);
+        assert!(dates("2026. október van").is_empty());
+        assert!(dates("2026. októberben 18.").is_empty());
+        assert!(dates("2026. 10 alma").is_empty());
+    }
+
+    #[test]
+    fn double_acute_letters() {
+        let tokens = lex("kettő hűtő ŐSZ ŰR");
+
+        assert_eq!(tokens, vec![
+            (PlainTextToken::Text, "kettő"),
+            (PlainTextToken::Text, "hűtő"),
+            (PlainTextToken::Text, "ŐSZ"),
+            (PlainTextToken::Text, "ŰR"),
+        ]);
+    }
+
+    #[test]
+    fn whole_alphabet() {
+        let tokens = lex("aábcdeéfghiíjklmnoóöőpqrstuúüűvwxyz AÁBCDEÉFGHIÍJKLMNOÓÖŐPQRSTUÚÜŰVWXYZ");
+
+        assert_eq!(tokens.len(), 2);
+        assert!(tokens.iter().all(|(token, _)| *token == PlainTextToken::Text));
+    }
+
+    #[test]
+    fn digraphs_and_foreign_letters() {
+        let tokens = lex("dzsungel Łódź Dvořák façade");
+
+        assert_eq!(tokens.len(), 4);
+        assert!(tokens.iter().all(|(token, _)| *token == PlainTextToken::Text));
+    }
+
+    #[test]
+    fn hyphenated_compounds() {
+        let tokens = lex("Kovács-Szabó kék-zöld, bor-");
+
+        assert_eq!(tokens, vec![
+            (PlainTextToken::Text, "Kovács-Szabó"),
+            (PlainTextToken::Text, "kék-zöld"),
+            (PlainTextToken::Comma, ","),
+            (PlainTextToken::Text, "bor"),
+            (PlainTextToken::Error, "-"),
+        ]);
+    }
+}
@@ -0,0 +1,219 @@
+use std::ops::Range;
+
+use crate::model::{Position, TokenKind, ValidationError};
+use crate::model::lex;
+use crate::segment::{default_segmenter, Segmenter};
+
+/// A token of a text: its kind, the slice of the text it was created from and its position.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    pub position: Position,
+}
+
+/// A text split into tokens, so that it can be processed by multiple detectors without lexing it
+/// again.
+#[derive(Clone, Debug)]
+pub struct TokenStream<'a> {
+    tokens: Vec<Token<'a>>,
+}
+
+impl<'a> TokenStream<'a> {
+    /// Splits the text using the built-in tokenizer.
+    pub fn new(text: &'a str) -> TokenStream<'a> {
+        TokenStream::from_tokens(Tokens::new(text))
+    }
+
+    /// Creates a stream from already tokenized text, e.g. from an other tokenizer (see
+    /// Tokens::from_spans) or from a pre-tokenized corpus.
+    pub fn from_tokens<I: IntoIterator<Item = Token<'a>>>(tokens: I) -> TokenStream<'a> {
+        TokenStream {
+            tokens: tokens.into_iter().collect(),
+        }
+    }
+
+    pub fn tokens(&self) -> &[Token<'a>] {
+        &self.tokens
+    }
+
+    pub fn iter(&self) -> std::slice::Iter<'_, Token<'a>> {
+        self.tokens.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+}
+
+/// The tokens of a text with their positions, created one by one as they are needed (see
+/// TokenStream for a text which is processed more than once).
+pub struct Tokens<'a> {
+    tokens: Box<dyn Iterator<Item = Token<'a>> + 'a>,
+}
+
+impl<'a> Tokens<'a> {
+    /// Splits the text using the built-in tokenizer. The periods after abbreviations are not
+    /// sentence ends (see Segmenter::hungarian_default).
+    pub fn new(text: &'a str) -> Tokens<'a> {
+        Tokens {
+            tokens: Box::new(default_segmenter().segment(Tokens::unsegmented(text))),
+        }
+    }
+
+    /// Splits the text using the built-in tokenizer and the given segmenter, e.g. one with other
+    /// abbreviations or paragraph breaks.
+    pub fn with_segmenter(text: &'a str, segmenter: &'a Segmenter) -> Tokens<'a> {
+        Tokens {
+            tokens: Box::new(segmenter.segment(Tokens::unsegmented(text))),
+        }
+    }
+
+    /// Uses the kinds and byte spans given by an other tokenizer, the lines and columns are
+    /// calculated from the text. The tokens are not segmented (see Segmenter::segment).
+    ///
+    /// Fails if a span starts before the end of the previous one, if it is not inside the text or
+    /// not on character boundaries.
+    pub fn from_spans<I>(text: &'a str, spans: I) -> Result<Tokens<'a>, ValidationError>
+    where
+        I: IntoIterator<Item = (TokenKind, Range<usize>)>,
+    {
+        let spans = spans.into_iter().collect::<Vec<_>>();
+
+        let mut end = 0;
+        for (index, (_, span)) in spans.iter().enumerate() {
+            let is_valid = end <= span.start && span.start <= span.end && span.end <= text.len()
+                && text.is_char_boundary(span.start) && text.is_char_boundary(span.end);
+            if !is_valid {
+                return Err(ValidationError::InvalidSpan { index, span: span.clone() });
+            }
+            end = span.end;
+        }
+
+        Ok(Tokens::from_valid_spans(text, spans))
+    }
+
+    /// Like from_spans, but the spans are known to be valid, e.g. they come from the built-in
+    /// tokenizer.
+    pub(crate) fn from_valid_spans<I>(text: &'a str, spans: I) -> Tokens<'a>
+    where
+        I: IntoIterator<Item = (TokenKind, Range<usize>)>,
+        I::IntoIter: 'a,
+    {
+        Tokens {
+            tokens: Box::new(Positions {
+                source: text,
+                spans: spans.into_iter(),
+                offset: 0,
+                line: 1,
+                col: 1,
+                utf16_col: 1,
+            }),
+        }
+    }
+
+    /// The tokens of the built-in tokenizer, every period is a sentence end.
+    pub(crate) fn unsegmented(text: &'a str) -> Tokens<'a> {
+        Tokens::from_valid_spans(text, lex(text))
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        self.tokens.next()
+    }
+}
+
+/// Calculates the positions of the tokens given by their kinds and spans.
+struct Positions<'a, I: Iterator<Item = (TokenKind, Range<usize>)>> {
+    source: &'a str,
+    spans: I,
+    offset: usize,
+    line: usize,
+    col: usize,
+    utf16_col: usize,
+}
+
+impl<'a, I: Iterator<Item = (TokenKind, Range<usize>)>> Iterator for Positions<'a, I> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let (kind, span) = self.spans.next()?;
+
+        for c in self.source[self.offset..span.start].chars() {
+            if c == '\n' {
+                self.line += 1;
+                self.col = 1;
+                self.utf16_col = 1;
+            } else {
+                self.col += 1;
+                self.utf16_col += c.len_utf16();
+            }
+        }
+        self.offset = span.start;
+
+        Some(Token {
+            kind,
+            text: &self.source[span.clone()],
+            position: Position { span, line: self.line, col: self.col, utf16_col: self.utf16_col },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{Position, TokenKind, Tokens, TokenStream, ValidationError};
+
+    #[test]
+    fn positions() {
+        let stream = TokenStream::new("Ő  jön,\n\thogy");
+        let tokens = stream.tokens();
+
+        assert_eq!(tokens.len(), 5);
+        assert_eq!((&tokens[0].kind, tokens[0].text), (&TokenKind::Text, "Ő"));
+        assert_eq!(tokens[0].position, Position { span: 0..2, line: 1, col: 1, utf16_col: 1 });
+        assert_eq!(tokens[1].position, Position { span: 4..8, line: 1, col: 4, utf16_col: 4 });
+        assert_eq!((&tokens[2].kind, tokens[2].position.line, tokens[2].position.col), (&TokenKind::Comma, 1, 7));
+        assert_eq!((&tokens[3].kind, tokens[3].position.line, tokens[3].position.col), (&TokenKind::NewLine, 1, 8));
+        assert_eq!(tokens[4].position, Position { span: 11..15, line: 2, col: 2, utf16_col: 2 });
+    }
+
+    #[test]
+    fn utf16_columns() {
+        let stream = TokenStream::new("😀 mondta hogy");
+        let hogy = &stream.tokens()[2];
+
+        assert_eq!(hogy.text, "hogy");
+        assert_eq!(hogy.position, Position { span: 12..16, line: 1, col: 10, utf16_col: 11 });
+    }
+
+    #[test]
+    fn custom_spans() {
+        let text = "Ő jön,\nhogy";
+        let tokens = Tokens::from_spans(text, vec![(TokenKind::Text, 0..2), (TokenKind::Text, 3..7), (TokenKind::Comma, 7..8), (TokenKind::Text, 9..13)])
+            .unwrap()
+            .collect::<Vec<_>>();
+
+        assert_eq!((tokens[1].kind, tokens[1].text), (TokenKind::Text, "jön"));
+        assert_eq!(tokens[1].position, Position { span: 3..7, line: 1, col: 3, utf16_col: 3 });
+        assert_eq!(tokens[3].position, Position { span: 9..13, line: 2, col: 1, utf16_col: 1 });
+    }
+
+    #[test]
+    fn invalid_spans() {
+        let text = "Ő jön,\nhogy";
+        let error = |spans: Vec<(TokenKind, std::ops::Range<usize>)>| Tokens::from_spans(text, spans).err();
+
+        assert_eq!(error(vec![(TokenKind::Text, 3..7), (TokenKind::Text, 0..2)]), Some(ValidationError::InvalidSpan { index: 1, span: 0..2 }));
+        assert_eq!(error(vec![(TokenKind::Text, 0..4), (TokenKind::Text, 3..7)]), Some(ValidationError::InvalidSpan { index: 1, span: 3..7 }));
+        assert_eq!(error(vec![(TokenKind::Text, 0..1)]), Some(ValidationError::InvalidSpan { index: 0, span: 0..1 }));
+        assert_eq!(error(vec![(TokenKind::Text, 9..20)]), Some(ValidationError::InvalidSpan { index: 0, span: 9..20 }));
+        assert!(error(vec![(TokenKind::Text, 0..2), (TokenKind::NewLine, 7..8)]).is_none());
+    }
+}
@@ -0,0 +1,68 @@
+/// The kind of a token of the text, this is what the detectors look at besides the text of the
+/// token.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    /// A number, e.g. `42` or `3,14`.
+    Number,
+    /// A punctuation mark ending a sentence: `.`, `?` or `!`.
+    EndOfSentence,
+    /// A comma or a semicolon.
+    Comma,
+    /// A colon, it can introduce a clause.
+    Colon,
+    /// A dash (`–`, `—`, or a hyphen surrounded by spaces), it can stand instead of a comma around
+    /// an inserted clause.
+    Dash,
+    /// An opening parenthesis or bracket.
+    OpenParen,
+    /// A closing parenthesis or bracket.
+    CloseParen,
+    /// A quotation mark opening a quote, e.g. `„`.
+    OpeningQuote,
+    /// A quotation mark closing a quote, e.g. `”`.
+    ClosingQuote,
+    /// An ellipsis (`…` or `...`) which doesn't end the sentence, otherwise it is an EndOfSentence
+    /// (see Segmenter).
+    Ellipsis,
+    /// A line break.
+    NewLine,
+    /// A line break which ends a paragraph, by default the one before an empty line (see
+    /// Segmenter::with_paragraph_breaks). The detectors treat it as the end of a sentence.
+    ParagraphBreak,
+    /// A word (hyphenated compounds are a single word).
+    Text,
+    /// A number followed by a period, e.g. `3.` in "3. fejezet".
+    Ordinal,
+    /// A date, e.g. `2026. október 18.`, `2026. okt. 18-án` or `2026.10.18.`.
+    Date,
+    /// A Roman numeral of at least two letters followed by a period, e.g. `II.` in "II. Rákóczi Ferenc".
+    RomanNumeral,
+    /// A capital letter followed by a period, e.g. `J.` in "J. K. Rowling".
+    Initial,
+    /// Anything else, e.g. a period after an abbreviation or a hyphen that is not a dash.
+    Other,
+}
+
+impl TokenKind {
+    /// True for the tokens which the detectors treat as words: words, numbers, ordinals, dates,
+    /// Roman numerals and initials.
+    pub fn is_word(&self) -> bool {
+        matches!(self, TokenKind::Text | TokenKind::Number | TokenKind::Ordinal | TokenKind::Date | TokenKind::RomanNumeral | TokenKind::Initial)
+    }
+
+    /// True for the punctuation which makes a comma before the next word unnecessary: a comma, a
+    /// colon, a dash or an opening parenthesis.
+    pub fn replaces_comma(&self) -> bool {
+        matches!(self, TokenKind::Comma | TokenKind::Colon | TokenKind::Dash | TokenKind::OpenParen)
+    }
+
+    /// True for the tokens after which a new sentence starts: the end of a sentence or a paragraph.
+    pub fn ends_sentence(&self) -> bool {
+        matches!(self, TokenKind::EndOfSentence | TokenKind::ParagraphBreak)
+    }
+
+    /// True for the line breaks, including the ones which end a paragraph.
+    pub fn is_line_break(&self) -> bool {
+        matches!(self, TokenKind::NewLine | TokenKind::ParagraphBreak)
+    }
+}
@@ -1,6 +1,12 @@
+use crate::model::{SettingsError, ValidationError};
+use crate::model::settings_parser::{settings_rows, strict, SettingsRow};
+use crate::model::validation::{check_phrases, check_words};
+
 /// Contains the words that are used in the TypicalDetector.
 ///
-/// Loads words from files.
+/// Loads words from files. Every row of the file has the following format: `word;probability`,
+/// everything after a `#` is a comment. The word can also be a phrase of multiple words (e.g.
+/// `mivel hogy;0.5`, see Phrase), its comma mark is not used by the TypicalDetector.
 #[derive(Clone)]
 pub struct TypicalSettings {
     pub words: Vec<String>,
@@ -8,24 +14,52 @@ pub struct TypicalSettings {
 }
 
 impl TypicalSettings {
-    pub fn new_from_string(content: String) -> TypicalSettings {
-        let rows = content.split('\n');
+    /// The bundled Hungarian dictionary of words that usually imply a comma in the sentence (see
+    /// `dictionaries/typical.csv` for the probabilities and their sources).
+    pub fn hungarian_default() -> TypicalSettings {
+        TypicalSettings::new_from_string(String::from(include_str!("../../dictionaries/typical.csv")))
+            .expect("the bundled dictionary is valid")
+    }
 
-        let mut words = Vec::new();
-        let mut probs = Vec::new();
+    /// Loads the settings, fails on the first row that is not valid.
+    pub fn new_from_string(content: String) -> Result<TypicalSettings, SettingsError> {
+        strict(TypicalSettings::new_from_string_lenient(content))
+    }
 
-        for row in rows {
-            let cols = row.split(';').collect::<Vec<&str>>();
+    /// Loads the settings, skipping every row that is not valid. The problems of these rows are
+    /// returned alongside the settings.
+    pub fn new_from_string_lenient(content: String) -> (TypicalSettings, Vec<SettingsError>) {
+        let mut settings = TypicalSettings {
+            words: Vec::new(),
+            probs: Vec::new(),
+        };
+        let mut errors = Vec::new();
 
-            if cols.len() > 1 {
-                words.push(String::from(cols[0]));
-                probs.push(cols[1].trim().parse::<f64>().unwrap());
+        for row in settings_rows(&content) {
+            if let Err(error) = settings.add_row(&row) {
+                errors.push(error);
             }
         }
 
-        TypicalSettings {
-            words,
-            probs,
-        }
+        (settings, errors)
+    }
+
+    /// Checks that every word has a probability, the words are unique, non-empty, valid phrases
+    /// and the probabilities are in the 0..=1 range.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        check_words("words", &self.words, &self.probs)?;
+        check_phrases(&self.words)
     }
-}
\ No newline at end of file
+
+    fn add_row(&mut self, row: &SettingsRow) -> Result<(), SettingsError> {
+        row.expect_columns(2, 2)?;
+        let word = row.phrase(0)?;
+        let prob = row.prob(1)?;
+        row.check_duplicate(0, &self.words)?;
+
+        self.words.push(word);
+        self.probs.push(prob);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,114 @@
+use std::error::Error;
+use std::ops::Range;
+use crate::model::{ContextException, Normalization, Phrase};
+use std::fmt;
+
+/// Describes why a settings struct (or the tokens of an other tokenizer) can't be used by a
+/// detector.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    /// Two parallel lists of the settings have different lengths.
+    LengthMismatch { field: &'static str, expected: usize, found: usize },
+    /// The word (or the list of words) at the given index is empty.
+    EmptyWord { field: &'static str, index: usize },
+    /// The probability at the given index is not in the 0..=1 range.
+    ProbabilityOutOfRange { index: usize, prob: f64 },
+    /// The word at the given index was already defined at a lower index.
+    DuplicateEntry { index: usize, word: String },
+    /// The word at the given index is not a valid phrase (see Phrase::parse).
+    InvalidPhrase { index: usize, word: String },
+    /// The span of the token at the given index is before the end of the previous one, it is not
+    /// inside the text or not on character boundaries (see Tokens::from_spans).
+    InvalidSpan { index: usize, span: Range<usize> },
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::LengthMismatch { field, expected, found } => {
+                write!(f, "\"{}\" has {} elements instead of {}", field, found, expected)
+            }
+            ValidationError::EmptyWord { field, index } => {
+                write!(f, "\"{}\" contains an empty word at index {}", field, index)
+            }
+            ValidationError::ProbabilityOutOfRange { index, prob } => {
+                write!(f, "the probability {} at index {} is not between 0 and 1", prob, index)
+            }
+            ValidationError::DuplicateEntry { index, word } => {
+                write!(f, "\"{}\" at index {} is already defined", word, index)
+            }
+            ValidationError::InvalidPhrase { index, word } => {
+                write!(f, "\"{}\" at index {} is not a valid phrase", word, index)
+            }
+            ValidationError::InvalidSpan { index, span } => {
+                write!(f, "the span {:?} at index {} is not a valid span of the text", span, index)
+            }
+        }
+    }
+}
+
+impl Error for ValidationError {}
+
+pub(crate) fn check_length(field: &'static str, expected: usize, found: usize) -> Result<(), ValidationError> {
+    if expected != found {
+        return Err(ValidationError::LengthMismatch { field, expected, found });
+    }
+
+    Ok(())
+}
+
+/// Checks the common parts of every settings: the words are non-empty and unique (after the
+/// default normalization, see Normalization), and each of them has a probability in the 0..=1
+/// range.
+pub(crate) fn check_words(field: &'static str, words: &[String], probs: &[f64]) -> Result<(), ValidationError> {
+    check_length("probs", words.len(), probs.len())?;
+
+    let normalized = Normalization::default().normalize_all(words);
+    for (index, word) in words.iter().enumerate() {
+        if word.trim().is_empty() {
+            return Err(ValidationError::EmptyWord { field, index });
+        }
+        if normalized[..index].contains(&normalized[index]) {
+            return Err(ValidationError::DuplicateEntry { index, word: word.clone() });
+        }
+    }
+
+    for (index, prob) in probs.iter().enumerate() {
+        if !(0.0..=1.0).contains(prob) {
+            return Err(ValidationError::ProbabilityOutOfRange { index, prob: *prob });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that every word is a valid phrase (a single word is valid if it isn't empty).
+pub(crate) fn check_phrases(words: &[String]) -> Result<(), ValidationError> {
+    for (index, word) in words.iter().enumerate() {
+        if Phrase::parse(word).is_none() {
+            return Err(ValidationError::InvalidPhrase { index, word: word.clone() });
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the exceptions are either missing or given for every word, and that their words are
+/// non-empty and their probabilities are in the 0..=1 range.
+pub(crate) fn check_exceptions(words: &[String], exceptions: &[Vec<ContextException>]) -> Result<(), ValidationError> {
+    if exceptions.is_empty() {
+        return Ok(());
+    }
+    check_length("exceptions", words.len(), exceptions.len())?;
+
+    for (index, exception) in exceptions.iter().enumerate().flat_map(|(index, a)| a.iter().map(move |b| (index, b))) {
+        if exception.word.trim().is_empty() {
+            return Err(ValidationError::EmptyWord { field: "exceptions", index });
+        }
+        if !(0.0..=1.0).contains(&exception.prob) {
+            return Err(ValidationError::ProbabilityOutOfRange { index, prob: exception.prob });
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,4 @@
+mod segmenter;
+
+pub use segmenter::{ParagraphBreaks, Segmented, Segmenter};
+pub(crate) use segmenter::default_segmenter;
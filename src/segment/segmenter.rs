@@ -0,0 +1,350 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::OnceLock;
+
+use crate::model::{AbbreviationSettings, Position, Token, TokenKind, Tokens, ValidationError};
+
+/// Finds the real sentence boundaries among the tokens.
+///
+/// The tokenizer treats every `.`, `?` and `!` as the end of a sentence. The segmenter turns the
+/// periods after abbreviations (e.g. "pl.", "dr.", "kb.") into TokenKind::Other, so the detectors
+/// don't lose their state in the middle of a sentence. The abbreviations which can end a sentence
+/// (e.g. "stb.") still end it if the next word is capitalized. A period at the end of the text or
+/// before an empty line always ends the sentence.
+///
+/// The ordinals and dates (e.g. "3.", "2026. október 18.") don't end the sentence either, unless
+/// the next word is capitalized. Roman numerals and initials (e.g. "II.", "J.") are usually followed
+/// by a name, so they only end it if the next word is a common sentence starter (see
+/// SENTENCE_STARTERS, e.g. "Ő. Aztán", "a C. Majd"), at the end of the text or before an empty
+/// line. If they end the sentence, their period is split off as a separate EndOfSentence token (and
+/// an ordinal becomes a Number, an initial a Text). An ellipsis ends the sentence
+/// (and becomes an EndOfSentence) if the next word is capitalized.
+///
+/// The line breaks which end a paragraph become TokenKind::ParagraphBreak (see ParagraphBreaks), so
+/// a heading or a list item without a final period doesn't continue in the next paragraph.
+pub struct Segmenter {
+    /// The lowercase abbreviations, and whether they can end a sentence.
+    abbreviations: HashMap<String, bool>,
+    paragraph_breaks: ParagraphBreaks,
+}
+
+/// Which line breaks end a paragraph.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum ParagraphBreaks {
+    /// The paragraphs are separated by empty lines (or lines containing only whitespace), the line
+    /// break before an empty line ends the paragraph.
+    #[default]
+    BlankLine,
+    /// Every line is a paragraph of its own, e.g. for lists or texts with hard line breaks
+    /// between the paragraphs.
+    LineBreak,
+}
+
+impl Segmenter {
+    /// Fails if the settings are not valid (see AbbreviationSettings::validate)
+    pub fn new(settings: AbbreviationSettings) -> Result<Segmenter, ValidationError> {
+        settings.validate()?;
+
+        Ok(Segmenter {
+            abbreviations: settings.words.iter()
+                .map(|a| a.to_lowercase())
+                .zip(settings.sentence_end.iter().cloned())
+                .collect(),
+            paragraph_breaks: ParagraphBreaks::default(),
+        })
+    }
+
+    pub fn with_paragraph_breaks(mut self, paragraph_breaks: ParagraphBreaks) -> Segmenter {
+        self.paragraph_breaks = paragraph_breaks;
+        self
+    }
+
+    /// Uses the bundled Hungarian abbreviations (see AbbreviationSettings::hungarian_default).
+    pub fn hungarian_default() -> Segmenter {
+        Segmenter::new(AbbreviationSettings::hungarian_default()).expect("the bundled dictionary is valid")
+    }
+
+    /// Segments the tokens lazily, only the periods after abbreviations, the periods of ordinals,
+    /// dates, Roman numerals and initials, and the line breaks are changed.
+    pub fn segment<'a, I: IntoIterator<Item = Token<'a>>>(&self, tokens: I) -> Segmented<'_, 'a, I::IntoIter> {
+        Segmented {
+            segmenter: self,
+            tokens: tokens.into_iter(),
+            buffer: VecDeque::new(),
+            following: Vec::new(),
+            previous: None,
+        }
+    }
+
+    /// Splits the text into sentences (using the built-in tokenizer), the whitespace between the
+    /// sentences is left out. The end of a paragraph ends the sentence as well.
+    pub fn sentences<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut sentences = Vec::new();
+        let mut start = None;
+        let mut end = 0;
+
+        for token in self.segment(Tokens::unsegmented(text)) {
+            if token.kind == TokenKind::ParagraphBreak {
+                if let Some(start) = start.take() {
+                    sentences.push(&text[start..end]);
+                }
+                continue;
+            }
+            if token.kind == TokenKind::NewLine {
+                continue;
+            }
+
+            let first = *start.get_or_insert(token.position.span.start);
+            end = token.position.span.end;
+            if token.kind == TokenKind::EndOfSentence {
+                sentences.push(&text[first..end]);
+                start = None;
+            }
+        }
+        if let Some(start) = start {
+            sentences.push(&text[start..end]);
+        }
+
+        sentences
+    }
+
+    /// Decides whether the period after the word ends the sentence (see is_sentence_end).
+    fn is_abbreviation_end(&self, word: &Token, following: &[&Token]) -> bool {
+        match self.abbreviations.get(&word.text.to_lowercase()) {
+            Some(sentence_end) => is_sentence_end(*sentence_end, following, self.paragraph_breaks),
+            None => true,
+        }
+    }
+}
+
+/// Capitalized words which are common at the start of a sentence, but are not names. A Roman
+/// numeral or an initial before them ends the sentence.
+const SENTENCE_STARTERS: &[&str] = &[
+    "a", "az", "egy", "ez", "ezt", "azt", "ezek", "azok", "de", "és", "s", "majd", "aztán", "akkor",
+    "utána", "ezért", "azért", "így", "tehát", "mert", "ha", "hogy", "nem", "igen", "most", "már",
+    "még", "is", "itt", "ott", "én", "te", "ő", "mi", "ti", "ők", "sőt", "pedig", "viszont", "persze",
+    "talán", "ki", "mit", "mikor", "hol", "miért",
+];
+
+/// True if the token is a capitalized word which usually starts a sentence (not a name).
+fn is_sentence_starter(token: &Token) -> bool {
+    token.kind == TokenKind::Text
+        && token.text.chars().next().is_some_and(char::is_uppercase)
+        && SENTENCE_STARTERS.contains(&token.text.to_lowercase().as_str())
+}
+
+/// Decides whether a period ends the sentence, based on the (at most two) tokens after it. It does
+/// at the end of the text and at the end of a paragraph, otherwise only if it `can_end` the sentence
+/// and the next word is capitalized.
+fn is_sentence_end(can_end: bool, following: &[&Token], paragraph_breaks: ParagraphBreaks) -> bool {
+    let next = match following {
+        [] => return true,
+        [Token { kind: TokenKind::ParagraphBreak, .. }, ..] => return true,
+        [newline, ..] if newline.kind.is_line_break() && paragraph_breaks == ParagraphBreaks::LineBreak => return true,
+        [newline] | [newline, Token { kind: TokenKind::NewLine, .. }, ..] if newline.kind == TokenKind::NewLine => return true,
+        [newline, next, ..] if newline.kind == TokenKind::NewLine => next,
+        [next, ..] => next,
+    };
+
+    can_end && next.text.chars().next().is_some_and(char::is_uppercase)
+}
+
+/// Splits the period off the end of the token.
+fn split_period<'a>(token: &Token<'a>) -> (Token<'a>, Token<'a>) {
+    let text = &token.text[..token.text.len() - 1];
+    let mut position = token.position.clone();
+    position.span.end -= 1;
+
+    let period = Token {
+        kind: TokenKind::EndOfSentence,
+        text: &token.text[text.len()..],
+        position: Position {
+            span: position.span.end..position.span.end + 1,
+            line: position.line,
+            col: position.col + text.chars().count(),
+            utf16_col: position.utf16_col + text.encode_utf16().count(),
+        },
+    };
+    let kind = match token.kind {
+        TokenKind::Ordinal => TokenKind::Number,
+        TokenKind::Initial => TokenKind::Text,
+        kind => kind,
+    };
+
+    (Token { kind, text, position }, period)
+}
+
+/// The Segmenter used by the built-in tokenizer.
+pub(crate) fn default_segmenter() -> &'static Segmenter {
+    static SEGMENTER: OnceLock<Segmenter> = OnceLock::new();
+
+    SEGMENTER.get_or_init(Segmenter::hungarian_default)
+}
+
+/// The tokens segmented by a Segmenter (see Segmenter::segment).
+///
+/// A period after an abbreviation (or an ordinal, etc.) is only given out after the next tokens are
+/// read, since they decide whether it ends the sentence.
+pub struct Segmented<'s, 'a, I: Iterator<Item = Token<'a>>> {
+    segmenter: &'s Segmenter,
+    tokens: I,
+    buffer: VecDeque<Token<'a>>,
+    following: Vec<Token<'a>>,
+    previous: Option<Token<'a>>,
+}
+
+impl<'s, 'a, I: Iterator<Item = Token<'a>>> Segmented<'s, 'a, I> {
+    /// The tokens which come after the given ones (e.g. the beginning of the next line), they are
+    /// only used to decide whether the last period ends the sentence, they are not given out.
+    pub fn followed_by(mut self, tokens: Vec<Token<'a>>) -> Segmented<'s, 'a, I> {
+        self.following = tokens;
+        self
+    }
+
+    /// Reads the next two tokens (if there are that many) into the buffer, and returns them
+    /// together with the following tokens.
+    fn look_ahead(&mut self) -> Vec<&Token<'a>> {
+        while self.buffer.len() < 2 {
+            match self.tokens.next() {
+                Some(next) => self.buffer.push_back(next),
+                None => break,
+            }
+        }
+
+        self.buffer.iter().chain(self.following.iter()).take(2).collect()
+    }
+
+    /// True if the line break (which was just taken out of the buffer) ends the paragraph.
+    fn is_paragraph_end(&mut self) -> bool {
+        match self.segmenter.paragraph_breaks {
+            ParagraphBreaks::BlankLine => matches!(self.look_ahead().first(), Some(next) if next.kind.is_line_break()),
+            ParagraphBreaks::LineBreak => true,
+        }
+    }
+
+    /// True if the token is a period right after a word.
+    fn is_period_after_word(&self, token: &Token) -> bool {
+        token.kind == TokenKind::EndOfSentence && token.text == "." && self.previous.as_ref()
+            .is_some_and(|a| a.kind == TokenKind::Text && a.position.span.end == token.position.span.start)
+    }
+}
+
+impl<'s, 'a, I: Iterator<Item = Token<'a>>> Iterator for Segmented<'s, 'a, I> {
+    type Item = Token<'a>;
+
+    fn next(&mut self) -> Option<Token<'a>> {
+        let mut token = self.buffer.pop_front().or_else(|| self.tokens.next())?;
+
+        if self.is_period_after_word(&token) {
+            let segmenter = self.segmenter;
+            let word = self.previous.take().expect("the period is after a word");
+            if !segmenter.is_abbreviation_end(&word, &self.look_ahead()) {
+                token.kind = TokenKind::Other;
+            }
+        } else if token.kind == TokenKind::Ellipsis {
+            let paragraph_breaks = self.segmenter.paragraph_breaks;
+            if is_sentence_end(true, &self.look_ahead(), paragraph_breaks) {
+                token.kind = TokenKind::EndOfSentence;
+            }
+        } else if token.text.ends_with('.') && matches!(token.kind, TokenKind::Ordinal | TokenKind::Date | TokenKind::RomanNumeral | TokenKind::Initial) {
+            let paragraph_breaks = self.segmenter.paragraph_breaks;
+            let following = self.look_ahead();
+            let can_end = matches!(token.kind, TokenKind::Ordinal | TokenKind::Date)
+                || following.iter().find(|a| !a.kind.is_line_break()).is_some_and(|a| is_sentence_starter(a));
+            if is_sentence_end(can_end, &following, paragraph_breaks) {
+                let (word, period) = split_period(&token);
+                self.buffer.push_front(period);
+                token = word;
+            }
+        }
+
+        if token.kind == TokenKind::NewLine && self.is_paragraph_end() {
+            token.kind = TokenKind::ParagraphBreak;
+        }
+
+        if !token.kind.is_line_break() {
+            self.previous = Some(token.clone());
+        }
+
+        Some(token)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::model::{AbbreviationSettings, Position, TokenKind, Tokens};
+    use crate::segment::{ParagraphBreaks, Segmenter};
+
+    fn kinds(text: &str) -> Vec<TokenKind> {
+        Tokens::new(text).map(|a| a.kind).collect()
+    }
+
+    #[test]
+    fn abbreviations() {
+        assert_eq!(kinds("Pl. alma."), vec![TokenKind::Text, TokenKind::Other, TokenKind::Text, TokenKind::EndOfSentence]);
+        assert_eq!(kinds("dr. Kovács"), vec![TokenKind::Text, TokenKind::Other, TokenKind::Text]);
+        assert_eq!(kinds("alma. Körte"), vec![TokenKind::Text, TokenKind::EndOfSentence, TokenKind::Text]);
+        assert_eq!(kinds("kb ."), vec![TokenKind::Text, TokenKind::EndOfSentence]);
+    }
+
+    #[test]
+    fn abbreviations_ending_sentences() {
+        assert_eq!(kinds("körte stb. Alma")[2], TokenKind::EndOfSentence);
+        assert_eq!(kinds("körte stb.\nAlma")[2], TokenKind::EndOfSentence);
+        assert_eq!(kinds("körte stb. vettünk")[2], TokenKind::Other);
+        assert_eq!(kinds("körte stb.")[2], TokenKind::EndOfSentence);
+        assert_eq!(kinds("kb.\n\nalma")[1], TokenKind::EndOfSentence);
+        assert_eq!(kinds("kb.\nalma")[1], TokenKind::Other);
+    }
+
+    #[test]
+    fn ordinals_and_dates() {
+        assert_eq!(kinds("A 2. pontban"), vec![TokenKind::Text, TokenKind::Ordinal, TokenKind::Text]);
+        assert_eq!(kinds("Ő lett a 2. Utána"), vec![TokenKind::Text, TokenKind::Text, TokenKind::Text, TokenKind::Number, TokenKind::EndOfSentence, TokenKind::Text]);
+        assert_eq!(kinds("II. Rákóczi és J. K. Rowling"), vec![TokenKind::RomanNumeral, TokenKind::Text, TokenKind::Text, TokenKind::Initial, TokenKind::Initial, TokenKind::Text]);
+        assert_eq!(kinds("Ma 2026. október 18. van"), vec![TokenKind::Text, TokenKind::Date, TokenKind::Text]);
+
+        let tokens = Tokens::new("Ma 2026. október 18.").collect::<Vec<_>>();
+        assert_eq!((tokens[1].kind, tokens[1].text), (TokenKind::Date, "2026. október 18"));
+        assert_eq!((tokens[2].kind, tokens[2].text), (TokenKind::EndOfSentence, "."));
+        assert_eq!(tokens[2].position, Position { span: 20..21, line: 1, col: 20, utf16_col: 20 });
+    }
+
+    #[test]
+    fn initials_ending_sentences() {
+        let tokens = Tokens::new("Ki jött? Ő. Aztán elment.").collect::<Vec<_>>();
+        assert_eq!((tokens[3].kind, tokens[3].text), (TokenKind::Text, "Ő"));
+        assert_eq!(tokens[4].kind, TokenKind::EndOfSentence);
+
+        assert_eq!(kinds("Kell a C. Majd meglátjuk.")[3], TokenKind::EndOfSentence);
+        assert_eq!(kinds("Ez a II. Majd a III.")[3], TokenKind::EndOfSentence);
+        assert_eq!(kinds("Ezt írta J. Rowling."), vec![TokenKind::Text, TokenKind::Text, TokenKind::Initial, TokenKind::Text, TokenKind::EndOfSentence]);
+        assert_eq!(kinds("J. A. Smith")[..2], [TokenKind::Initial, TokenKind::Initial]);
+        assert_eq!(kinds("Szabó T. Anna")[1], TokenKind::Initial);
+    }
+
+    #[test]
+    fn ellipsis() {
+        assert_eq!(kinds("Hát... nem tudom... Talán"), vec![TokenKind::Text, TokenKind::Ellipsis, TokenKind::Text, TokenKind::Text, TokenKind::EndOfSentence, TokenKind::Text]);
+        assert_eq!(kinds("Nem tudom…")[2], TokenKind::EndOfSentence);
+    }
+
+    #[test]
+    fn sentences() {
+        let segmenter = Segmenter::new(AbbreviationSettings { words: vec![String::from("pl")], sentence_end: vec![false] }).unwrap();
+
+        assert_eq!(segmenter.sentences("Pl. alma és körte.\nDe stb. nem! "), vec!["Pl. alma és körte.", "De stb.", "nem!"]);
+        assert_eq!(segmenter.sentences("  Nincs vége"), vec!["Nincs vége"]);
+        assert!(segmenter.sentences("\n").is_empty());
+    }
+
+    #[test]
+    fn paragraph_breaks() {
+        assert_eq!(kinds("Cím\n \nSzöveg\nfolytatás\n"), vec![TokenKind::Text, TokenKind::ParagraphBreak, TokenKind::NewLine, TokenKind::Text, TokenKind::NewLine, TokenKind::Text, TokenKind::NewLine]);
+
+        let segmenter = Segmenter::hungarian_default().with_paragraph_breaks(ParagraphBreaks::LineBreak);
+        let tokens = Tokens::with_segmenter("Cím\nszöveg kb.\nalma", &segmenter).map(|a| a.kind).collect::<Vec<_>>();
+        assert_eq!(tokens, vec![TokenKind::Text, TokenKind::ParagraphBreak, TokenKind::Text, TokenKind::Text, TokenKind::EndOfSentence, TokenKind::ParagraphBreak, TokenKind::Text]);
+        assert_eq!(segmenter.sentences("- alma\n- körte."), vec!["- alma", "- körte."]);
+        assert_eq!(Segmenter::hungarian_default().sentences("Cím\n\nSzöveg"), vec!["Cím", "Szöveg"]);
+    }
+}
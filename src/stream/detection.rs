@@ -0,0 +1,86 @@
+use std::collections::VecDeque;
+
+use crate::model::{Mistake, Token};
+use crate::traits::Detector;
+
+/// The mistakes found by a detector, computed lazily: the tokens are only processed (and lexed)
+/// until the next mistake is found, so the iteration can be stopped early without processing the
+/// rest of the text.
+///
+/// The mistakes are yielded in the order they are found, the same way detect_errors_in_stream
+/// would return them.
+pub struct DetectionIter<'d, 'a, D: Detector + ?Sized, I: Iterator<Item = Token<'a>>> {
+    detector: &'d mut D,
+    tokens: I,
+    errors: VecDeque<(usize, usize, Mistake)>,
+    finished: bool,
+}
+
+impl<'d, 'a, D: Detector + ?Sized, I: Iterator<Item = Token<'a>>> DetectionIter<'d, 'a, D, I> {
+    /// Resets the detector, so that it can process the tokens from the start.
+    pub fn new(detector: &'d mut D, tokens: I) -> DetectionIter<'d, 'a, D, I> {
+        detector.reset();
+
+        DetectionIter {
+            detector,
+            tokens,
+            errors: VecDeque::new(),
+            finished: false,
+        }
+    }
+}
+
+impl<'d, 'a, D: Detector + ?Sized, I: Iterator<Item = Token<'a>>> Iterator for DetectionIter<'d, 'a, D, I> {
+    type Item = (usize, usize, Mistake);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut errors = Vec::new();
+
+        while self.errors.is_empty() && !self.finished {
+            match self.tokens.next() {
+                Some(token) => self.detector.next_token(&token, &mut errors),
+                None => {
+                    self.detector.end_of_row(&mut errors);
+                    self.detector.end_of_text(&mut errors);
+                    self.finished = true;
+                }
+            }
+            self.errors.extend(errors.drain(..));
+        }
+
+        self.errors.pop_front()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::detector::naive_fixture;
+    use crate::model::{Token, TokenStream, Tokens};
+    use crate::stream::DetectionIter;
+    use crate::traits::Detector;
+
+    #[test]
+    fn same_as_stream() {
+        let text = "Azt mondta hogy jön. Ha esik akkor otthon marad.\nTudom hogy";
+        let expected = naive_fixture().detect_errors_in_stream(&TokenStream::new(text));
+        let errors = naive_fixture().detect_iter(text).collect::<Vec<_>>();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(
+            errors.iter().map(|(row, col, a)| (*row, *col, a.position.clone())).collect::<Vec<_>>(),
+            expected.iter().map(|(row, col, a)| (*row, *col, a.position.clone())).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn stops_early() {
+        let text = "Azt mondta hogy jön. Ha esik akkor otthon marad.";
+        let mut detector = naive_fixture();
+        let mut processed = 0;
+        let first = DetectionIter::new(&mut detector, Tokens::new(text).inspect(|_: &Token| processed += 1))
+            .find(|(_, _, a)| a.prob > 0.8);
+
+        assert_eq!(first.map(|(_, _, a)| a.position.span), Some(11..15));
+        assert_eq!(processed, 3);
+    }
+}
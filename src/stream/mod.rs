@@ -0,0 +1,5 @@
+mod detection;
+mod reader;
+
+pub use detection::DetectionIter;
+pub use reader::ReaderDetection;
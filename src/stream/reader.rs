@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead};
+
+use crate::model::{Mistake, Tokens};
+use crate::segment::{default_segmenter, Segmenter};
+use crate::traits::Detector;
+
+/// Runs a detector on a text read from a BufRead, without loading the whole text into memory.
+///
+/// The text is read line by line, and the detector keeps its state between the lines, so the
+/// results are the same as if the whole text was given to detect_errors_in_stream: sentences can
+/// span multiple lines and every position (line, column and byte span) is relative to the start
+/// of the whole text. The mistakes of a line are yielded as soon as it is processed (which needs
+/// the next line to be read, see Segmenter), the mistakes which wait for the next token are yielded
+/// at the end of the text.
+///
+/// The lines are segmented by the segmenter of the built-in tokenizer, unless an other one is given
+/// (see with_segmenter).
+///
+/// If the reader fails (or the text is not valid UTF-8), the error is yielded and the iteration
+/// stops.
+pub struct ReaderDetection<'d, D: Detector + ?Sized, R: BufRead> {
+    detector: &'d mut D,
+    reader: R,
+    segmenter: &'d Segmenter,
+    /// The line to be processed, and the one after it.
+    line: String,
+    next_line: String,
+    started: bool,
+    /// The number of bytes and lines read so far.
+    offset: usize,
+    lines: usize,
+    errors: VecDeque<(usize, usize, Mistake)>,
+    finished: bool,
+}
+
+impl<'d, D: Detector + ?Sized, R: BufRead> ReaderDetection<'d, D, R> {
+    /// Resets the detector, so that it can process the text from the start.
+    pub fn new(detector: &'d mut D, reader: R) -> ReaderDetection<'d, D, R> {
+        detector.reset();
+
+        ReaderDetection {
+            detector,
+            reader,
+            segmenter: default_segmenter(),
+            line: String::new(),
+            next_line: String::new(),
+            started: false,
+            offset: 0,
+            lines: 0,
+            errors: VecDeque::new(),
+            finished: false,
+        }
+    }
+
+    /// Uses the given segmenter (e.g. one with other abbreviations or paragraph breaks) instead of
+    /// the default one.
+    pub fn with_segmenter(mut self, segmenter: &'d Segmenter) -> ReaderDetection<'d, D, R> {
+        self.segmenter = segmenter;
+        self
+    }
+
+    /// Processes the next line, returns false at the end of the text.
+    fn next_line(&mut self) -> io::Result<bool> {
+        let mut errors = Vec::new();
+
+        if !self.started {
+            self.started = true;
+            self.reader.read_line(&mut self.line)?;
+        }
+        if self.line.is_empty() {
+            self.detector.end_of_row(&mut errors);
+            self.detector.end_of_text(&mut errors);
+            self.errors.extend(errors);
+            return Ok(false);
+        }
+
+        self.next_line.clear();
+        self.reader.read_line(&mut self.next_line)?;
+
+        // The line contains at most one line break, at its end, so every token is in its first line
+        let following = Tokens::unsegmented(&self.next_line).take(2).collect();
+        for mut token in self.segmenter.segment(Tokens::unsegmented(&self.line)).followed_by(following) {
+            token.position.span = token.position.span.start + self.offset..token.position.span.end + self.offset;
+            token.position.line = self.lines + 1;
+
+            self.detector.next_token(&token, &mut errors);
+        }
+
+        self.offset += self.line.len();
+        self.lines += 1;
+        self.errors.extend(errors);
+        std::mem::swap(&mut self.line, &mut self.next_line);
+
+        Ok(true)
+    }
+}
+
+impl<'d, D: Detector + ?Sized, R: BufRead> Iterator for ReaderDetection<'d, D, R> {
+    type Item = io::Result<(usize, usize, Mistake)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.errors.is_empty() && !self.finished {
+            match self.next_line() {
+                Ok(more) => self.finished = !more,
+                Err(error) => {
+                    self.finished = true;
+                    return Some(Err(error));
+                }
+            }
+        }
+
+        self.errors.pop_front().map(Ok)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{BufReader, ErrorKind};
+
+    use crate::detector::{naive_fixture, PairDetector};
+    use crate::model::{AbbreviationSettings, PairSettings, TokenStream, Tokens};
+    use crate::segment::Segmenter;
+    use crate::stream::ReaderDetection;
+    use crate::traits::Detector;
+
+    #[test]
+    fn same_as_whole_text() {
+        let text = "Ha esik\nakkor otthon maradok.\r\nŐ mondta\thogy jön stb.\nde kb.\n\nTudom hogy";
+        let expected = naive_fixture().detect_errors_in_stream(&TokenStream::new(text));
+
+        // A tiny buffer, so that the lines are read in multiple chunks
+        let mut detector = naive_fixture();
+        let errors = ReaderDetection::new(&mut detector, BufReader::with_capacity(3, text.as_bytes()))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(errors.len(), 3);
+        assert_eq!(errors.len(), expected.len());
+        for ((row, col, mistake), (expected_row, expected_col, expected_mistake)) in errors.iter().zip(expected.iter()) {
+            assert_eq!((row, col), (expected_row, expected_col));
+            assert_eq!(mistake.position, expected_mistake.position);
+            assert_eq!(mistake.insert_at, expected_mistake.insert_at);
+        }
+        assert_eq!(errors[1].2.position.span, 41..45);
+    }
+
+    #[test]
+    fn state_kept_between_lines() {
+        let mut detector = PairDetector::new(PairSettings { first_words: vec![String::from("ha")], second_words: vec![vec![String::from("akkor")]], probs: vec![0.8], options: Vec::new(), further_parts: Vec::new() }).unwrap();
+        let errors = ReaderDetection::new(&mut detector, "Ha esik\nakkor otthon maradok.".as_bytes())
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!((errors[0].0, errors[0].1), (2, 1));
+        assert_eq!(errors[0].2.position.span, 8..13);
+    }
+
+    #[test]
+    fn custom_segmenter() {
+        // Without abbreviations "pl." ends the sentence, so "hogy" starts the next one
+        let text = "Azt mondta pl.\nhogy jön.";
+        let segmenter = Segmenter::new(AbbreviationSettings { words: Vec::new(), sentence_end: Vec::new() }).unwrap();
+
+        let mut detector = naive_fixture();
+        let default_errors = ReaderDetection::new(&mut detector, text.as_bytes()).count();
+        let errors = ReaderDetection::new(&mut detector, text.as_bytes())
+            .with_segmenter(&segmenter)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+
+        assert_eq!(default_errors, 1);
+        assert_eq!(errors.len(), naive_fixture().detect_errors_in_stream(&TokenStream::from_tokens(Tokens::with_segmenter(text, &segmenter))).len());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn invalid_utf8() {
+        let mut detector = naive_fixture();
+        let text: &[u8] = b"Tudom hogy\nmegy.\nj\xf6n.";
+        let mut errors = ReaderDetection::new(&mut detector, text);
+
+        assert_eq!(errors.next().unwrap().unwrap().2.position.span, 6..10);
+        assert!(matches!(errors.next(), Some(Err(error)) if error.kind() == ErrorKind::InvalidData));
+        assert!(errors.next().is_none());
+    }
+}
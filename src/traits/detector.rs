@@ -1,9 +1,87 @@
-use logos::Lexer;
+use std::io::BufRead;
 
-use crate::model::{PlainTextToken, Mistake};
+use crate::model::{Mistake, Token, Tokens, TokenStream};
+use crate::stream::{DetectionIter, ReaderDetection};
 
+/// Detectors are driven token by token, so that more of them can process the same TokenStream
+/// (see Analyzer) in a single pass.
 pub trait Detector {
-    fn detect_errors(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)>;
+    /// Resets the state of the detector, so that a new text can be processed.
+    fn reset(&mut self);
 
-    fn detect_errors_in_row(&mut self, tokens: &mut Lexer<PlainTextToken>) -> Vec<(usize, usize, Mistake)>;
-}
\ No newline at end of file
+    /// Processes the next token of the text, the found mistakes are appended to `errors`.
+    fn next_token(&mut self, token: &Token, errors: &mut Vec<(usize, usize, Mistake)>);
+
+    /// Signals the end of a row given to detect_errors_in_row. The sentence can continue in the
+    /// next row, so the mistakes which wait for the next tokens are kept until the end of the text.
+    fn end_of_row(&mut self, errors: &mut Vec<(usize, usize, Mistake)>);
+
+    /// Signals the end of the text, after the end of its last row. The mistakes which were waiting
+    /// for the next tokens (e.g. the ones of a last sentence without a period) are appended to
+    /// `errors`.
+    fn end_of_text(&mut self, _errors: &mut Vec<(usize, usize, Mistake)>) {}
+
+    /// Processes a whole text, split by the built-in tokenizer.
+    fn detect_errors(&mut self, text: &str) -> Vec<(usize, usize, Mistake)> {
+        self.reset();
+
+        let mut errors = self.detect_errors_in_row(text);
+        self.end_of_text(&mut errors);
+
+        errors
+    }
+
+    /// Processes the next row of a text, the state of the detector is kept between the rows. The
+    /// lines of the mistakes count the previous rows too, but their byte spans are relative to the
+    /// row. After the last row, end_of_text has to be called to get the mistakes which wait for the
+    /// rest of the sentence.
+    fn detect_errors_in_row(&mut self, row: &str) -> Vec<(usize, usize, Mistake)> {
+        let mut errors = Vec::new();
+
+        for token in Tokens::new(row) {
+            self.next_token(&token, &mut errors);
+        }
+        self.end_of_row(&mut errors);
+
+        errors
+    }
+
+    /// Processes an already tokenized text (see TokenStream::from_tokens for other tokenizers).
+    fn detect_errors_in_stream(&mut self, tokens: &TokenStream) -> Vec<(usize, usize, Mistake)> {
+        self.reset();
+
+        let mut errors = Vec::new();
+        for token in tokens.iter() {
+            self.next_token(token, &mut errors);
+        }
+        self.end_of_row(&mut errors);
+        self.end_of_text(&mut errors);
+
+        errors
+    }
+
+    /// Processes the prose of a Markdown document, the positions of the mistakes are in the
+    /// Markdown source (see markdown::prose_tokens).
+    #[cfg(feature = "markdown")]
+    fn detect_errors_in_markdown(&mut self, source: &str) -> Vec<(usize, usize, Mistake)> {
+        self.detect_errors_in_stream(&TokenStream::from_tokens(crate::markdown::prose_tokens(source)))
+    }
+
+    /// Processes the text lazily, the mistakes are found as the iterator is advanced (see
+    /// DetectionIter).
+    fn detect_iter<'t>(&mut self, text: &'t str) -> DetectionIter<'_, 't, Self, Tokens<'t>>
+    where
+        Self: Sized,
+    {
+        DetectionIter::new(self, Tokens::new(text))
+    }
+
+    /// Processes a text read line by line from the reader, the mistakes are yielded as the text is
+    /// read (see ReaderDetection).
+    fn detect_errors_in_reader<R: BufRead>(&mut self, reader: R) -> ReaderDetection<'_, Self, R>
+    where
+        Self: Sized,
+    {
+        ReaderDetection::new(self, reader)
+    }
+}